@@ -1,17 +1,373 @@
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::Local;
+use clap::Parser;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, SampleFormat, StreamConfig};
 use hound::{self, WavWriter};
+use num_complex::Complex32;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 use rubato::FftFixedInOut;
 use rubato::Resampler;
 use std::fs::{self, File};
 use std::io::{self, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
-fn get_device() -> Result<Device> {
+/// Headless capture of the mic_activity demo. When a flag is supplied the
+/// matching interactive prompt is skipped; omit it to keep the old
+/// menu-driven behavior.
+#[derive(Parser, Debug)]
+#[command(name = "mic_activity", about)]
+struct Cli {
+    /// Microphone to record from, matched by exact name or by enumeration index.
+    #[arg(long)]
+    device: Option<String>,
+    /// Recording duration in seconds. Omit together with --until-silence to
+    /// be prompted interactively.
+    #[arg(long)]
+    duration: Option<u64>,
+    /// Record until the VAD detects a sustained silence instead of a fixed duration.
+    #[arg(long)]
+    until_silence: bool,
+    /// Where to write the captured WAV file.
+    #[arg(long, default_value = "output.wav")]
+    output: PathBuf,
+    /// Transcription API URL named for each saved utterance when --transcribe is set.
+    #[arg(long)]
+    api_url: Option<String>,
+    /// Print, for each saved utterance, the --api-url it would be sent to.
+    /// No transcription request is made.
+    #[arg(long, default_value_t = false)]
+    transcribe: bool,
+    /// Overrides --transcribe, so nothing is printed for saved utterances.
+    #[arg(long, default_value_t = false)]
+    no_transcribe: bool,
+    /// Skip the interactive "mute your mic and press Enter" prompt before
+    /// recording a muted-sample calibration; assumes the mic is already muted.
+    #[arg(long, default_value_t = false)]
+    skip_mute_confirmation: bool,
+}
+
+/// Speech band used for the FFT-based VAD, in Hz.
+const SPEECH_BAND_HZ: (f32, f32) = (300.0, 3400.0);
+
+/// Thresholds governing the spectral VAD. These sit alongside the legacy
+/// RMS `threshold_margin` and are tuned independently.
+#[derive(Clone, Copy, Debug)]
+struct SpectralVadConfig {
+    /// dB the speech-band SNR must clear above the noise floor to count as speech.
+    speech_band_snr_margin_db: f32,
+    /// Spectral flatness (0..1) below which a frame is considered tonal/voiced.
+    flatness_threshold: f32,
+}
+
+impl Default for SpectralVadConfig {
+    fn default() -> Self {
+        SpectralVadConfig {
+            speech_band_snr_margin_db: 6.0,
+            flatness_threshold: 0.5,
+        }
+    }
+}
+
+/// Per-bin Hann window, frame-based magnitude spectrum analyzer backed by `realfft`.
+struct SpectralVad {
+    config: SpectralVadConfig,
+    sample_rate: f32,
+    frame_len: usize,
+    window: Vec<f32>,
+    fft: std::sync::Arc<dyn realfft::RealToComplex<f32>>,
+    noise_floor: Vec<f32>,
+}
+
+impl SpectralVad {
+    /// Builds a VAD whose noise floor is seeded from the average magnitude
+    /// spectrum of a calibration "muted" WAV clip.
+    fn from_muted_sample(
+        muted_sample_path: &Path,
+        sample_rate: u32,
+        config: SpectralVadConfig,
+    ) -> Result<Self> {
+        // ~25ms frames.
+        let frame_len = ((sample_rate as f32) * 0.025).round() as usize;
+        let window = hann_window(frame_len);
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(frame_len);
+
+        let mut reader =
+            hound::WavReader::open(muted_sample_path).context("Failed to open muted sample")?;
+        let spec = reader.spec();
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Int => reader
+                .samples::<i16>()
+                .map(|s| s.unwrap() as f32 / i16::MAX as f32)
+                .collect(),
+            hound::SampleFormat::Float => reader.samples::<f32>().map(|s| s.unwrap()).collect(),
+        };
+
+        let mut floor_sum = vec![0.0f32; frame_len / 2 + 1];
+        let mut floor_frames = 0usize;
+        for chunk in samples.chunks(frame_len) {
+            if chunk.len() < frame_len {
+                break;
+            }
+            let magnitude = magnitude_spectrum(&fft, &window, chunk);
+            for (sum, mag) in floor_sum.iter_mut().zip(magnitude.iter()) {
+                *sum += mag;
+            }
+            floor_frames += 1;
+        }
+        if floor_frames > 0 {
+            for sum in floor_sum.iter_mut() {
+                *sum /= floor_frames as f32;
+            }
+        }
+
+        Ok(SpectralVad {
+            config,
+            sample_rate: sample_rate as f32,
+            frame_len,
+            window,
+            fft,
+            noise_floor: floor_sum,
+        })
+    }
+
+    /// Returns true if `frame` (exactly `frame_len` mono samples) looks like speech.
+    fn is_speech(&self, frame: &[f32]) -> bool {
+        if frame.len() != self.frame_len {
+            return false;
+        }
+        let magnitude = magnitude_spectrum(&self.fft, &self.window, frame);
+
+        let bin_hz = self.sample_rate / self.frame_len as f32;
+        let (low_bin, high_bin) = (
+            (SPEECH_BAND_HZ.0 / bin_hz).floor() as usize,
+            ((SPEECH_BAND_HZ.1 / bin_hz).ceil() as usize).min(magnitude.len() - 1),
+        );
+
+        let mut band_energy = 0.0f32;
+        let mut band_noise = 0.0f32;
+        let mut total_energy = 0.0f32;
+        for (k, &mag) in magnitude.iter().enumerate() {
+            total_energy += mag * mag;
+            if k >= low_bin && k <= high_bin {
+                band_energy += mag * mag;
+                band_noise += self.noise_floor[k] * self.noise_floor[k];
+            }
+        }
+        if total_energy <= f32::EPSILON {
+            return false;
+        }
+
+        let snr_db = 10.0 * (band_energy.max(f32::EPSILON) / band_noise.max(f32::EPSILON)).log10();
+        let flatness = spectral_flatness(&magnitude);
+
+        snr_db > self.config.speech_band_snr_margin_db && flatness < self.config.flatness_threshold
+    }
+}
+
+fn hann_window(len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|n| {
+            0.5 - 0.5
+                * (2.0 * std::f32::consts::PI * n as f32 / (len.saturating_sub(1)).max(1) as f32)
+                    .cos()
+        })
+        .collect()
+}
+
+fn magnitude_spectrum(
+    fft: &std::sync::Arc<dyn realfft::RealToComplex<f32>>,
+    window: &[f32],
+    frame: &[f32],
+) -> Vec<f32> {
+    let mut windowed: Vec<f32> = frame
+        .iter()
+        .zip(window.iter())
+        .map(|(&s, &w)| s * w)
+        .collect();
+    let mut spectrum: Vec<Complex32> = fft.make_output_vec();
+    fft.process(&mut windowed, &mut spectrum)
+        .expect("FFT processing failed");
+    spectrum.iter().map(|c| c.norm()).collect()
+}
+
+/// Geometric mean / arithmetic mean of the magnitude bins. Near 1.0 for
+/// noise-like spectra, low for tonal/voiced content.
+fn spectral_flatness(magnitude: &[f32]) -> f32 {
+    let n = magnitude.len() as f32;
+    if n == 0.0 {
+        return 1.0;
+    }
+    let floor = 1e-9f32;
+    let log_sum: f32 = magnitude.iter().map(|&m| (m.max(floor)).ln()).sum();
+    let geometric_mean = (log_sum / n).exp();
+    let arithmetic_mean = magnitude.iter().sum::<f32>() / n;
+    if arithmetic_mean <= floor {
+        1.0
+    } else {
+        geometric_mean / arithmetic_mean
+    }
+}
+
+/// Tuning for the spectral-subtraction denoiser.
+#[derive(Clone, Copy, Debug)]
+struct SpectralSubtractionConfig {
+    enabled: bool,
+    /// Over-subtraction factor (~1.5-2.0); higher suppresses more noise at
+    /// the cost of speech distortion.
+    over_subtraction_alpha: f32,
+    /// Spectral floor (~0.02) that keeps residual "musical noise" inaudible
+    /// instead of driving bins to exactly zero.
+    spectral_floor_beta: f32,
+}
+
+impl Default for SpectralSubtractionConfig {
+    fn default() -> Self {
+        SpectralSubtractionConfig {
+            enabled: true,
+            over_subtraction_alpha: 1.8,
+            spectral_floor_beta: 0.02,
+        }
+    }
+}
+
+/// Classic spectral-subtraction denoiser: the muted calibration clip
+/// supplies a per-bin noise magnitude profile `N[k]`, which is subtracted
+/// (with an over-subtraction factor and a spectral floor to avoid musical
+/// noise) from each incoming frame before overlap-adding back to a clean
+/// time-domain signal.
+struct SpectralDenoiser {
+    config: SpectralSubtractionConfig,
+    frame_len: usize,
+    hop_len: usize,
+    window: Vec<f32>,
+    fft: std::sync::Arc<dyn RealToComplex<f32>>,
+    ifft: std::sync::Arc<dyn ComplexToReal<f32>>,
+    noise_profile: Vec<f32>,
+    input_buffer: Vec<f32>,
+    overlap_tail: Vec<f32>,
+}
+
+impl SpectralDenoiser {
+    fn from_muted_sample(
+        muted_sample_path: &Path,
+        sample_rate: u32,
+        config: SpectralSubtractionConfig,
+    ) -> Result<Self> {
+        let frame_len = ((sample_rate as f32) * 0.025).round() as usize;
+        let hop_len = frame_len / 2;
+        let window = hann_window(frame_len);
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(frame_len);
+        let ifft = planner.plan_fft_inverse(frame_len);
+
+        let mut reader =
+            hound::WavReader::open(muted_sample_path).context("Failed to open muted sample")?;
+        let spec = reader.spec();
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Int => reader
+                .samples::<i16>()
+                .map(|s| s.unwrap() as f32 / i16::MAX as f32)
+                .collect(),
+            hound::SampleFormat::Float => reader.samples::<f32>().map(|s| s.unwrap()).collect(),
+        };
+
+        let mut profile_sum = vec![0.0f32; frame_len / 2 + 1];
+        let mut profile_frames = 0usize;
+        let mut offset = 0;
+        while offset + frame_len <= samples.len() {
+            let magnitude = magnitude_spectrum(&fft, &window, &samples[offset..offset + frame_len]);
+            for (sum, mag) in profile_sum.iter_mut().zip(magnitude.iter()) {
+                *sum += mag;
+            }
+            profile_frames += 1;
+            offset += hop_len;
+        }
+        if profile_frames > 0 {
+            for sum in profile_sum.iter_mut() {
+                *sum /= profile_frames as f32;
+            }
+        }
+
+        Ok(SpectralDenoiser {
+            config,
+            frame_len,
+            hop_len,
+            window,
+            fft,
+            ifft,
+            noise_profile: profile_sum,
+            input_buffer: Vec::new(),
+            overlap_tail: vec![0.0; frame_len - hop_len],
+        })
+    }
+
+    /// Denoises as many full frames as `input` (appended to any leftover
+    /// samples from the previous call) supports, returning the overlap-added
+    /// result. Trailing samples not yet long enough for a frame are buffered
+    /// for the next call.
+    fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if !self.config.enabled {
+            return input.to_vec();
+        }
+        self.input_buffer.extend_from_slice(input);
+
+        let mut output = Vec::new();
+        while self.input_buffer.len() >= self.frame_len {
+            let frame = &self.input_buffer[..self.frame_len];
+            let mut windowed: Vec<f32> = frame
+                .iter()
+                .zip(self.window.iter())
+                .map(|(&s, &w)| s * w)
+                .collect();
+
+            let mut spectrum: Vec<Complex32> = self.fft.make_output_vec();
+            self.fft
+                .process(&mut windowed, &mut spectrum)
+                .expect("forward FFT failed");
+
+            for (k, bin) in spectrum.iter_mut().enumerate() {
+                let magnitude = bin.norm();
+                let noise = self.noise_profile.get(k).copied().unwrap_or(0.0);
+                let cleaned_magnitude = (magnitude - self.config.over_subtraction_alpha * noise)
+                    .max(self.config.spectral_floor_beta * magnitude);
+                *bin = Complex32::from_polar(cleaned_magnitude, bin.arg());
+            }
+
+            let mut time_domain: Vec<f32> = self.ifft.make_output_vec();
+            self.ifft
+                .process(&mut spectrum, &mut time_domain)
+                .expect("inverse FFT failed");
+
+            // realfft's inverse transform is unnormalized; re-apply the
+            // analysis window as the synthesis window for overlap-add.
+            let norm = 1.0 / self.frame_len as f32;
+            let mut frame_out: Vec<f32> = time_domain
+                .iter()
+                .zip(self.window.iter())
+                .map(|(&v, &w)| v * norm * w)
+                .collect();
+
+            for (i, tail) in self.overlap_tail.iter().enumerate() {
+                frame_out[i] += tail;
+            }
+
+            output.extend_from_slice(&frame_out[..self.hop_len]);
+            self.overlap_tail = frame_out[self.hop_len..].to_vec();
+
+            self.input_buffer.drain(..self.hop_len);
+        }
+        output
+    }
+}
+
+fn get_device(cli: &Cli) -> Result<Device> {
     // Get the default host for audio devices
     let host = cpal::default_host();
 
@@ -25,6 +381,23 @@ fn get_device() -> Result<Device> {
         bail!("No input devices available");
     }
 
+    if let Some(wanted) = &cli.device {
+        // Match by exact device name first, then fall back to treating the
+        // argument as an enumeration index so a persisted mic name or a
+        // quick `--device 0` both work.
+        let matched_index = devices
+            .iter()
+            .position(|d| d.name().unwrap_or_default() == *wanted)
+            .or_else(|| wanted.parse::<usize>().ok().filter(|i| *i < devices.len()));
+        return match matched_index {
+            Some(index) => devices
+                .into_iter()
+                .nth(index)
+                .ok_or_else(|| anyhow!("device somehow not present???")),
+            None => bail!("No input device matching '{}'", wanted),
+        };
+    }
+
     let mut device_index: usize;
     loop {
         // List the available input devices
@@ -60,7 +433,16 @@ fn get_device() -> Result<Device> {
     Ok(device)
 }
 
-fn get_recording_duration() -> Result<Duration> {
+/// Returns `None` when the caller asked to record until silence rather than
+/// for a fixed duration.
+fn get_recording_duration(cli: &Cli) -> Result<Option<Duration>> {
+    if cli.until_silence {
+        return Ok(None);
+    }
+    if let Some(secs) = cli.duration {
+        return Ok(Some(Duration::from_secs(secs)));
+    }
+
     // Prompt the user to enter the recording duration in seconds
     print!("Please enter the recording duration in seconds: ");
     io::stdout().flush()?;
@@ -72,7 +454,7 @@ fn get_recording_duration() -> Result<Duration> {
         .parse()
         .context("Please enter a valid number")?;
     let duration = Duration::from_secs(duration_secs);
-    Ok(duration)
+    Ok(Some(duration))
 }
 
 fn get_device_stream_config(device: &Device) -> Result<StreamConfig> {
@@ -85,7 +467,7 @@ fn get_device_stream_config(device: &Device) -> Result<StreamConfig> {
     Ok(device_stream_config)
 }
 
-fn get_muted_amplitude(device: &Device) -> Result<f64> {
+fn get_muted_amplitude(device: &Device, cli: &Cli) -> Result<(f64, std::path::PathBuf)> {
     let device_name = device.name().unwrap_or("Unknown".to_string());
     let device_stream_config = get_device_stream_config(device)?;
 
@@ -102,13 +484,20 @@ fn get_muted_amplitude(device: &Device) -> Result<f64> {
                 .context("Failed to create directory for muted samples")?;
         }
 
-        // Prompt user to mute microphone
-        println!(
-            "No muted sample found for '{}'. Please mute your microphone and press Enter to record a 5-second muted sample.",
-            device_name
-        );
-        let mut dummy_input = String::new();
-        io::stdin().read_line(&mut dummy_input)?;
+        // Prompt user to mute microphone, unless told to skip the wait.
+        if cli.skip_mute_confirmation {
+            println!(
+                "No muted sample found for '{}'. Recording a 5-second muted sample (assuming it's already muted).",
+                device_name
+            );
+        } else {
+            println!(
+                "No muted sample found for '{}'. Please mute your microphone and press Enter to record a 5-second muted sample.",
+                device_name
+            );
+            let mut dummy_input = String::new();
+            io::stdin().read_line(&mut dummy_input)?;
+        }
 
         fn record_sample(
             device: &cpal::Device,
@@ -183,31 +572,116 @@ fn get_muted_amplitude(device: &Device) -> Result<f64> {
     }
     // Load muted sample and calculate its amplitude
     let muted_sample_amplitude = calculate_muted_sample_amplitude(&muted_sample_path)?;
-    Ok(muted_sample_amplitude)
+    Ok((muted_sample_amplitude, muted_sample_path))
 }
 
 // Shared state structure
 struct SharedState {
     activity_level_history: Vec<f64>,
     is_muted: bool,
+    /// Resampled 16kHz samples not yet long enough to form a full VAD frame.
+    vad_frame_buffer: Vec<f32>,
+    segmenter: UtteranceSegmenter,
 }
 
 const SAMPLE_RATE: usize = 16_000;
+/// How much audio before voice onset to retain so word beginnings aren't clipped.
+const PRE_ROLL: Duration = Duration::from_millis(300);
+
+/// Accumulates 16kHz mono samples into discrete utterances driven by the
+/// VAD's speech/silence calls, finishing a clip once `idle_samples`
+/// consecutive silent samples have elapsed. A small pre-roll ring buffer is
+/// kept so the moment speech starts isn't clipped.
+struct UtteranceSegmenter {
+    idle_samples: usize,
+    pre_roll_samples: usize,
+    active: bool,
+    idle_run: usize,
+    data_so_far: Vec<f32>,
+    pre_roll: std::collections::VecDeque<f32>,
+}
+
+impl UtteranceSegmenter {
+    fn new(idle_samples: usize, pre_roll_samples: usize) -> Self {
+        UtteranceSegmenter {
+            idle_samples,
+            pre_roll_samples,
+            active: false,
+            idle_run: 0,
+            data_so_far: Vec::new(),
+            pre_roll: std::collections::VecDeque::with_capacity(pre_roll_samples),
+        }
+    }
+
+    /// Feeds one VAD-sized frame and its speech/silence verdict. Returns a
+    /// finished utterance's samples once a silence run closes it.
+    fn push_frame(&mut self, frame: &[f32], is_speech: bool) -> Option<Vec<f32>> {
+        for &sample in frame {
+            self.pre_roll.push_back(sample);
+            if self.pre_roll.len() > self.pre_roll_samples {
+                self.pre_roll.pop_front();
+            }
+        }
+
+        if is_speech {
+            if !self.active {
+                self.active = true;
+                self.data_so_far.extend(self.pre_roll.iter().copied());
+            }
+            self.idle_run = 0;
+            self.data_so_far.extend_from_slice(frame);
+            None
+        } else if self.active {
+            self.data_so_far.extend_from_slice(frame);
+            self.idle_run += frame.len();
+            if self.idle_run >= self.idle_samples {
+                self.active = false;
+                self.idle_run = 0;
+                Some(std::mem::take(&mut self.data_so_far))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
 
 fn main() -> Result<()> {
-    let device = get_device()?;
+    let cli = Cli::parse();
+
+    let device = get_device(&cli)?;
     let device_name = device.name().unwrap_or("Unknown".to_string());
     println!("Selected device: {}", device_name);
 
-    let recording_duration = get_recording_duration()?;
-    println!("Recording for {} seconds...", recording_duration.as_secs());
+    let recording_duration = get_recording_duration(&cli)?;
+    match recording_duration {
+        Some(duration) => println!("Recording for {} seconds...", duration.as_secs()),
+        None => println!("Recording until silence..."),
+    }
 
-    let muted_sample_amplitude = get_muted_amplitude(&device)?;
+    let (muted_sample_amplitude, muted_sample_path) = get_muted_amplitude(&device, &cli)?;
     println!("Muted sample amplitude: {:.6}", muted_sample_amplitude);
 
+    let vad = SpectralVad::from_muted_sample(
+        &muted_sample_path,
+        SAMPLE_RATE as u32,
+        SpectralVadConfig::default(),
+    )
+    .context("Failed to build spectral VAD from muted sample")?;
+    let vad = Arc::new(vad);
+
+    let denoiser = SpectralDenoiser::from_muted_sample(
+        &muted_sample_path,
+        SAMPLE_RATE as u32,
+        SpectralSubtractionConfig::default(),
+    )
+    .context("Failed to build spectral denoiser from muted sample")?;
+    let denoiser = Arc::new(Mutex::new(denoiser));
+
     // Create a WAV writer to write the audio data
     let writer = WavWriter::create(
-        "output.wav",
+        &cli.output,
         hound::WavSpec {
             channels: 1,                               // Mono
             sample_rate: SAMPLE_RATE as u32,           // 16kHz
@@ -220,55 +694,129 @@ fn main() -> Result<()> {
     // Set up recording parameters
     let device_stream_config = get_device_stream_config(&device)?;
 
+    // Segment the continuous stream into per-utterance clips: keep
+    // accumulating while the VAD says speech, and close the clip out once a
+    // full second of silence (the same `samples_until_idle` default used by
+    // `MicrophoneConfig`, here expressed at the 16kHz VAD rate) has elapsed.
+    const SAMPLES_UNTIL_IDLE_AT_16K: usize = SAMPLE_RATE; // 1 second
+    let pre_roll_samples = (PRE_ROLL.as_secs_f32() * SAMPLE_RATE as f32).round() as usize;
+    let utterances_dir = Path::new("utterances");
+    fs::create_dir_all(utterances_dir).context("Failed to create utterances directory")?;
+
     // Shared state for activity level and mute detection
     let shared_state = Arc::new(Mutex::new(SharedState {
         activity_level_history: Vec::new(),
         is_muted: false,
+        vad_frame_buffer: Vec::new(),
+        segmenter: UtteranceSegmenter::new(SAMPLES_UNTIL_IDLE_AT_16K, pre_roll_samples),
     }));
 
-    // Define constants
-    const INPUT_SAMPLE_RATE: usize = 48000;
+    // Derive resampler parameters from the device's actual stream config
+    // rather than assuming 48kHz stereo; this is frequently wrong (44.1kHz,
+    // 16kHz, or multichannel devices all produce garbage with a hardcoded
+    // 48000 input rate).
+    let input_sample_rate = device_stream_config.sample_rate.0 as usize;
+    let input_channels = device_stream_config.channels as usize;
     const OUTPUT_SAMPLE_RATE: usize = SAMPLE_RATE;
-    const CHANNELS: usize = 1;
-    const FRAMES_PER_BUFFER: usize = 441; // https://github.com/HEnquist/rubato/issues/76#issuecomment-1966452981
-
-    // Initialize the resampler
-    let resampler = FftFixedInOut::<f32>::new(
-        INPUT_SAMPLE_RATE,
-        OUTPUT_SAMPLE_RATE,
-        FRAMES_PER_BUFFER,
-        CHANNELS,
-    )
-    .context("Failed to create resampler")?;
+    // rubato's FftFixedInOut wants an input chunk size; ~10ms of audio at the
+    // input rate satisfies its fixed-input constraint across the sample
+    // rates we actually see in the wild (44100, 48000, 16000, ...).
+    let frames_per_buffer = (input_sample_rate / 100).max(1);
+    let needs_resampling = input_sample_rate != OUTPUT_SAMPLE_RATE;
+
+    // Initialize the resampler (only when the device doesn't already hand us 16kHz).
+    let resampler = if needs_resampling {
+        Some(
+            FftFixedInOut::<f32>::new(input_sample_rate, OUTPUT_SAMPLE_RATE, frames_per_buffer, 1)
+                .context("Failed to create resampler")?,
+        )
+    } else {
+        None
+    };
     let resampler = Arc::new(Mutex::new(resampler));
+
+    // Completed utterances are handed off to a worker thread so saving the
+    // clip (and, eventually, transcribing it) never blocks the audio callback.
+    let (utterance_tx, utterance_rx) = std::sync::mpsc::channel::<Vec<f32>>();
+    let should_transcribe = cli.transcribe && !cli.no_transcribe;
+    let transcribe_api_url = cli.api_url.clone();
+    let utterance_worker = {
+        let utterances_dir = utterances_dir.to_path_buf();
+        thread::spawn(move || {
+            for utterance in utterance_rx {
+                match save_utterance_wav(&utterances_dir, &utterance) {
+                    Ok(path) => {
+                        println!(
+                            "Saved utterance ({} samples) to {}",
+                            utterance.len(),
+                            path.display()
+                        );
+                        if should_transcribe {
+                            match &transcribe_api_url {
+                                // This demo binary only captures and segments
+                                // audio; wire the file up to the same
+                                // transcription client the main app uses if
+                                // you need an end-to-end pipeline from here.
+                                Some(api_url) => println!(
+                                    "Would send {} to {} for transcription",
+                                    path.display(),
+                                    api_url
+                                ),
+                                None => eprintln!(
+                                    "--transcribe was passed without --api-url; skipping transcription"
+                                ),
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to save utterance: {:#}", e),
+                }
+            }
+        })
+    };
+
     let stream = {
         let writer_clone = writer.clone();
         let shared_state_clone = shared_state.clone();
         let resampler_clone = resampler.clone(); // To be defined
+        let vad_clone = vad.clone();
+        let denoiser_clone = denoiser.clone();
+        let utterance_tx = utterance_tx.clone();
         device.build_input_stream(
             &device_stream_config,
             move |data: &[f32], _: &_| {
-                // Downmix to mono
-                fn downmix_to_mono_f32(stereo_samples: &[f32]) -> Vec<f32> {
-                    stereo_samples
-                        .chunks(2)
-                        .map(|chunk| (chunk[0] + chunk[1]) / 2.0)
+                // Downmix to mono, averaging across however many channels the
+                // device actually exposes (not just assumed stereo pairs).
+                fn downmix_to_mono_f32(samples: &[f32], channels: usize) -> Vec<f32> {
+                    if channels <= 1 {
+                        return samples.to_vec();
+                    }
+                    samples
+                        .chunks(channels)
+                        .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
                         .collect()
                 }
-                let mono_data = downmix_to_mono_f32(data);
+                let mono_data = downmix_to_mono_f32(data, input_channels);
 
-                // Lock and process resampling
+                // Lock and process resampling, skipping entirely when the
+                // input already matches the 16kHz target.
                 let mut resampler_guard = resampler_clone.lock().unwrap();
-                let resampled_data = resampler_guard
-                    .process(&[mono_data], None)
-                    .expect("Resampling failed")
-                    .into_iter()
-                    .flatten()
-                    .collect::<Vec<f32>>();
+                let resampled_data = match resampler_guard.as_mut() {
+                    Some(resampler) => resampler
+                        .process(&[mono_data], None)
+                        .expect("Resampling failed")
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<f32>>(),
+                    None => mono_data,
+                };
                 drop(resampler_guard);
 
-                // Write resampled data
-                write_input_data_f32(&resampled_data, &writer_clone).unwrap();
+                // Clean the resampled signal using the noise profile captured
+                // from the muted calibration clip before it ever reaches disk.
+                let denoised_data = denoiser_clone.lock().unwrap().process(&resampled_data);
+
+                // Write denoised data
+                write_input_data_f32(&denoised_data, &writer_clone).unwrap();
                 // Analyze data to detect mute/unmute
 
                 fn calculate_rms_amplitude_f32(data: &[f32]) -> f32 {
@@ -291,9 +839,35 @@ fn main() -> Result<()> {
                 let sum: f64 = state.activity_level_history.iter().sum();
                 let moving_average = sum / state.activity_level_history.len() as f64;
 
-                // Determine mute state
+                // Determine mute state via the legacy RMS gate (kept for diagnostics)...
                 let threshold_margin = 0.001;
-                state.is_muted = moving_average <= muted_sample_amplitude + threshold_margin;
+                let rms_says_muted = moving_average <= muted_sample_amplitude + threshold_margin;
+
+                // ...but defer to the spectral VAD, which is far less prone to
+                // flagging steady broadband noise (fans, HVAC) as speech.
+                state.vad_frame_buffer.extend_from_slice(&denoised_data);
+                let mut saw_speech = false;
+                let mut saw_silence = false;
+                while state.vad_frame_buffer.len() >= vad_clone.frame_len {
+                    let frame: Vec<f32> = state.vad_frame_buffer.drain(..vad_clone.frame_len).collect();
+                    let is_speech = vad_clone.is_speech(&frame);
+                    if is_speech {
+                        saw_speech = true;
+                    } else {
+                        saw_silence = true;
+                    }
+                    if let Some(utterance) = state.segmenter.push_frame(&frame, is_speech) {
+                        let _ = utterance_tx.send(utterance);
+                    }
+                }
+                if saw_speech {
+                    state.is_muted = false;
+                } else if saw_silence {
+                    state.is_muted = true;
+                } else {
+                    // Not enough samples yet for a full frame; fall back to RMS.
+                    state.is_muted = rms_says_muted;
+                }
 
                 // For debugging: print current moving average and mute state
                 // println!(
@@ -308,14 +882,17 @@ fn main() -> Result<()> {
     // Start the input stream
     stream.play().context("Failed to start input stream")?;
 
-    // Record audio for the specified duration and monitor mute state
+    // Record audio for the specified duration and monitor mute state, or
+    // until sustained silence when --until-silence was requested.
+    const UNTIL_SILENCE_HANGOVER: Duration = Duration::from_secs(2);
     let start_time = Instant::now();
     let print_interval = Duration::from_secs(1);
     let mut last_print_time = Instant::now();
     let mut prev_mute_state = None;
+    let mut heard_speech = false;
+    let mut muted_since: Option<Instant> = None;
 
-    while Instant::now().duration_since(start_time) < recording_duration {
-        // Only check every print_interval
+    loop {
         if Instant::now().duration_since(last_print_time) >= print_interval {
             let state = shared_state.lock().unwrap();
             if prev_mute_state != Some(state.is_muted) {
@@ -323,11 +900,29 @@ fn main() -> Result<()> {
                     println!("Microphone has been muted");
                 } else {
                     println!("Microphone has been unmuted");
+                    heard_speech = true;
                 }
                 prev_mute_state = Some(state.is_muted);
             }
+            if state.is_muted {
+                muted_since.get_or_insert_with(Instant::now);
+            } else {
+                muted_since = None;
+            }
             last_print_time = Instant::now();
         }
+
+        let stop = match recording_duration {
+            Some(duration) => Instant::now().duration_since(start_time) >= duration,
+            None => {
+                heard_speech
+                    && muted_since
+                        .is_some_and(|since| since.elapsed() >= UNTIL_SILENCE_HANGOVER)
+            }
+        };
+        if stop {
+            break;
+        }
         thread::sleep(Duration::from_millis(100));
     }
 
@@ -340,10 +935,37 @@ fn main() -> Result<()> {
         writer.finalize()?;
     }
 
-    println!("Recording saved to output.wav");
+    println!("Recording saved to {}", cli.output.display());
+
+    // Let the worker drain any utterances still in flight, then wait for it
+    // to finish before exiting.
+    drop(utterance_tx);
+    utterance_worker
+        .join()
+        .map_err(|_| anyhow!("Utterance worker thread panicked"))?;
+
     Ok(())
 }
 
+/// Writes a single closed-out utterance to its own timestamped mono 16kHz
+/// WAV file inside `dir`, returning the path written.
+fn save_utterance_wav(dir: &Path, samples: &[f32]) -> Result<PathBuf> {
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S%3f");
+    let path = dir.join(format!("utterance_{}.wav", timestamp));
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: SAMPLE_RATE as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = WavWriter::create(&path, spec).context("Failed to create utterance WAV")?;
+    for &sample in samples {
+        writer.write_sample(sample)?;
+    }
+    writer.finalize().context("Failed to finalize utterance WAV")?;
+    Ok(path)
+}
+
 fn write_input_data_f32(
     input: &[f32],
     writer: &Arc<Mutex<Option<WavWriter<BufWriter<File>>>>>,
@@ -1,18 +1,519 @@
+use chrono::Local;
+use clap::Parser;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::Device;
+use directories::ProjectDirs;
 use hound;
+use rubato::{
+    Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::fs;
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
+
+/// Target sample rate for the optional `--resample-16k` output, matched to
+/// what Whisper-style speech-to-text models expect.
+const RESAMPLE_OUTPUT_HZ: u32 = 16_000;
+/// `SincFixedIn` wants a fixed input frame count per `process()` call.
+const RESAMPLE_CHUNK_FRAMES: usize = 1024;
+
+/// How long a run of silence must persist before a captured utterance clip
+/// is finalized and handed off.
+const SEGMENT_HANGOVER: Duration = Duration::from_millis(700);
+/// How much audio before voice onset to retain so word beginnings aren't clipped.
+const SEGMENT_PRE_ROLL: Duration = Duration::from_millis(300);
+
+/// Downmixes interleaved samples to mono by averaging across `channels`.
+fn downmix_to_mono_f32(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels)
+        .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Margin added to the calibrated muted-sample RMS before flipping back to
+/// "unmuted". A single value works across every format now that amplitude
+/// is normalized to `[-1, 1]` before comparison.
+const MUTE_THRESHOLD_MARGIN: f64 = 0.001;
+
+/// Bridges a cpal sample format to WAV-writing and amplitude analysis so
+/// `run_capture`/`build_passthrough_stream` only have to be written once.
+/// Adding a new cpal sample format (e.g. I32/F64) means adding an impl here
+/// instead of a fourth copy of the capture closure.
+trait CaptureSample: cpal::Sample + Copy + Send + 'static {
+    const HOUND_FORMAT: hound::SampleFormat;
+    const BITS_PER_SAMPLE: u16;
+
+    /// Writes this sample in whatever on-disk representation `HOUND_FORMAT`
+    /// describes (WAV sample type, not necessarily `Self`).
+    fn write_wav_sample(
+        self,
+        writer: &mut hound::WavWriter<std::io::BufWriter<std::fs::File>>,
+    ) -> Result<(), hound::Error>;
+
+    /// Normalizes to the `[-1, 1]` range used uniformly for RMS analysis,
+    /// downmixing, and resampling, regardless of on-the-wire format.
+    fn to_analysis_f32(self) -> f32;
+}
+
+impl CaptureSample for f32 {
+    const HOUND_FORMAT: hound::SampleFormat = hound::SampleFormat::Float;
+    const BITS_PER_SAMPLE: u16 = 32;
+
+    fn write_wav_sample(
+        self,
+        writer: &mut hound::WavWriter<std::io::BufWriter<std::fs::File>>,
+    ) -> Result<(), hound::Error> {
+        writer.write_sample(self)
+    }
+
+    fn to_analysis_f32(self) -> f32 {
+        self
+    }
+}
+
+impl CaptureSample for i16 {
+    const HOUND_FORMAT: hound::SampleFormat = hound::SampleFormat::Int;
+    const BITS_PER_SAMPLE: u16 = 16;
+
+    fn write_wav_sample(
+        self,
+        writer: &mut hound::WavWriter<std::io::BufWriter<std::fs::File>>,
+    ) -> Result<(), hound::Error> {
+        writer.write_sample(self)
+    }
+
+    fn to_analysis_f32(self) -> f32 {
+        self as f32 / i16::MAX as f32
+    }
+}
+
+impl CaptureSample for u16 {
+    const HOUND_FORMAT: hound::SampleFormat = hound::SampleFormat::Int;
+    const BITS_PER_SAMPLE: u16 = 16;
+
+    fn write_wav_sample(
+        self,
+        writer: &mut hound::WavWriter<std::io::BufWriter<std::fs::File>>,
+    ) -> Result<(), hound::Error> {
+        // Recenter u16 around 0 the same way i16 WAV samples are stored.
+        writer.write_sample((self as i32 - 32768) as i16)
+    }
+
+    fn to_analysis_f32(self) -> f32 {
+        (self as i32 - 32768) as f32 / 32768.0
+    }
+}
+
+/// Builds the WAV spec for `sample_format`, looking up bit depth and hound's
+/// on-disk format from the matching `CaptureSample` impl so the two stay in
+/// sync with `write_wav_sample` automatically.
+fn capture_wav_spec(channels: u16, sample_rate: u32, sample_format: cpal::SampleFormat) -> hound::WavSpec {
+    let (bits_per_sample, hound_sample_format) = match sample_format {
+        cpal::SampleFormat::F32 => (f32::BITS_PER_SAMPLE, f32::HOUND_FORMAT),
+        cpal::SampleFormat::I16 => (i16::BITS_PER_SAMPLE, i16::HOUND_FORMAT),
+        cpal::SampleFormat::U16 => (u16::BITS_PER_SAMPLE, u16::HOUND_FORMAT),
+        _ => panic!("Unsupported sample format"),
+    };
+    hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample,
+        sample_format: hound_sample_format,
+    }
+}
+
+/// RMS of already-normalized `[-1, 1]` samples.
+fn rms_amplitude(data: &[f32]) -> f64 {
+    let sum_squares: f64 = data.iter().map(|&sample| (sample as f64).powi(2)).sum();
+    (sum_squares / data.len() as f64).sqrt()
+}
+
+/// Headless capture of the mic_activity demo. When `--device` is supplied
+/// the interactive device/duration prompts, and the muted-sample
+/// calibration prompt, are all skipped, so the tool can run unattended from
+/// scripts or services; omit it to keep the original menu-driven behavior.
+#[derive(Parser, Debug)]
+#[command(name = "mic_activity", about)]
+struct Cli {
+    /// Microphone to record from, matched by exact name or by enumeration index.
+    #[arg(long)]
+    device: Option<String>,
+    /// Recording duration in seconds. Omitted alongside `--device` means
+    /// record until the process is stopped.
+    #[arg(long)]
+    duration: Option<u64>,
+    /// Where to write the captured WAV file.
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// List available input devices and exit.
+    #[arg(long, default_value_t = false)]
+    list_devices: bool,
+    /// Directory holding (or to create) the muted-microphone calibration samples.
+    #[arg(long)]
+    muted_sample_dir: Option<PathBuf>,
+    /// Also emit a 16kHz mono WAV alongside the raw capture.
+    #[arg(long, default_value_t = false)]
+    resample_16k: bool,
+}
+
+/// Defaults persisted across runs so repeat invocations don't need every flag
+/// re-specified. CLI flags always take precedence over these when given.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ExampleConfig {
+    /// Overrides the auto-generated `RecordingSession` path when set;
+    /// otherwise each take gets its own UUID filename under the project
+    /// data dir.
+    output: Option<PathBuf>,
+    muted_sample_dir: PathBuf,
+    resample_16k: bool,
+}
+
+impl Default for ExampleConfig {
+    fn default() -> Self {
+        ExampleConfig {
+            output: None,
+            muted_sample_dir: PathBuf::from("muted_microphone_samples"),
+            resample_16k: false,
+        }
+    }
+}
+
+impl ExampleConfig {
+    /// Loads the config from the OS config directory, writing out the
+    /// defaults on first run the same way `AppConfig::load` does for the
+    /// main binary.
+    fn load_or_default() -> Self {
+        let Some(path) = Self::config_path() else {
+            return ExampleConfig::default();
+        };
+        if !path.exists() {
+            let config = ExampleConfig::default();
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Ok(data) = serde_json::to_string_pretty(&config) {
+                let _ = fs::write(&path, data);
+            }
+            return config;
+        }
+        match fs::read_to_string(&path).ok().and_then(|data| serde_json::from_str(&data).ok()) {
+            Some(config) => config,
+            None => ExampleConfig::default(),
+        }
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        ProjectDirs::from("ca", "teamdman", "mic_activity_example")
+            .map(|dirs| dirs.config_dir().join("config.json"))
+    }
+}
+
+/// Downmixes incoming cpal buffers to mono and resamples them to
+/// `RESAMPLE_OUTPUT_HZ` for downstream speech-to-text, writing the result as
+/// i16 PCM to its own WAV file alongside the raw capture.
+struct Resample16k {
+    resampler: SincFixedIn<f32>,
+    input_channels: usize,
+    frame_buffer: Vec<f32>,
+    writer: Arc<Mutex<Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>>>,
+}
+
+impl Resample16k {
+    fn new(input_sample_rate: u32, input_channels: usize, output_path: &Path) -> Self {
+        let params = SincInterpolationParameters {
+            sinc_len: 256,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let resample_ratio = RESAMPLE_OUTPUT_HZ as f64 / input_sample_rate as f64;
+        let resampler = SincFixedIn::<f32>::new(resample_ratio, 2.0, params, RESAMPLE_CHUNK_FRAMES, 1)
+            .expect("Failed to create 16kHz resampler");
+
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: RESAMPLE_OUTPUT_HZ,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let wav_writer = hound::WavWriter::create(output_path, spec).unwrap();
+
+        Resample16k {
+            resampler,
+            input_channels,
+            frame_buffer: Vec::new(),
+            writer: Arc::new(Mutex::new(Some(wav_writer))),
+        }
+    }
+
+    /// Downmixes `data` (interleaved, `input_channels` wide) to mono, buffers
+    /// it, and drains every full `RESAMPLE_CHUNK_FRAMES`-frame chunk through
+    /// the resampler as it fills.
+    fn push(&mut self, data: &[f32]) {
+        self.frame_buffer
+            .extend(downmix_to_mono_f32(data, self.input_channels));
+
+        while self.frame_buffer.len() >= RESAMPLE_CHUNK_FRAMES {
+            let chunk: Vec<f32> = self.frame_buffer.drain(..RESAMPLE_CHUNK_FRAMES).collect();
+            let output = self
+                .resampler
+                .process(&[chunk], None)
+                .expect("16kHz resampling failed");
+            self.write_output(&output[0]);
+        }
+    }
+
+    /// Resamples whatever partial chunk is left once the stream stops.
+    fn flush(&mut self) {
+        if self.frame_buffer.is_empty() {
+            return;
+        }
+        let remainder = std::mem::take(&mut self.frame_buffer);
+        let output = self
+            .resampler
+            .process_partial(Some(&[remainder]), None)
+            .expect("16kHz resampler flush failed");
+        self.write_output(&output[0]);
+    }
+
+    fn write_output(&self, samples: &[f32]) {
+        let mut guard = self.writer.lock().unwrap();
+        if let Some(ref mut writer) = *guard {
+            for &sample in samples {
+                let scaled = (sample * i16::MAX as f32).clamp(i16::MIN as f32, i16::MAX as f32);
+                writer.write_sample(scaled as i16).unwrap();
+            }
+        }
+    }
+
+    fn finalize(self) {
+        let wav_writer = self.writer.lock().unwrap().take();
+        if let Some(writer) = wav_writer {
+            writer.finalize().unwrap();
+        }
+    }
+}
+
+/// Accumulates mono f32 samples into discrete utterance clips driven by the
+/// muted/unmuted verdict already tracked in `SharedState`, closing a clip out
+/// once silence has held for `SEGMENT_HANGOVER`. A small pre-roll ring buffer
+/// is kept so the moment speech starts isn't clipped.
+struct UtteranceSegmenter {
+    hangover_samples: usize,
+    pre_roll_samples: usize,
+    active: bool,
+    idle_run: usize,
+    data_so_far: Vec<f32>,
+    pre_roll: VecDeque<f32>,
+}
+
+impl UtteranceSegmenter {
+    fn new(sample_rate: u32) -> Self {
+        let hangover_samples = (SEGMENT_HANGOVER.as_secs_f32() * sample_rate as f32).round() as usize;
+        let pre_roll_samples = (SEGMENT_PRE_ROLL.as_secs_f32() * sample_rate as f32).round() as usize;
+        UtteranceSegmenter {
+            hangover_samples,
+            pre_roll_samples,
+            active: false,
+            idle_run: 0,
+            data_so_far: Vec::new(),
+            pre_roll: VecDeque::with_capacity(pre_roll_samples),
+        }
+    }
+
+    /// Feeds one chunk of mono samples and the muted/unmuted verdict covering
+    /// it. Returns a finished utterance's samples once a silence run closes
+    /// it out.
+    fn push(&mut self, samples: &[f32], is_muted: bool) -> Option<Vec<f32>> {
+        for &sample in samples {
+            self.pre_roll.push_back(sample);
+            if self.pre_roll.len() > self.pre_roll_samples {
+                self.pre_roll.pop_front();
+            }
+        }
+
+        if !is_muted {
+            if !self.active {
+                self.active = true;
+                self.data_so_far.extend(self.pre_roll.iter().copied());
+            }
+            self.idle_run = 0;
+            self.data_so_far.extend_from_slice(samples);
+            None
+        } else if self.active {
+            self.data_so_far.extend_from_slice(samples);
+            self.idle_run += samples.len();
+            if self.idle_run >= self.hangover_samples {
+                self.active = false;
+                self.idle_run = 0;
+                Some(std::mem::take(&mut self.data_so_far))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes a single closed-out utterance to its own timestamped mono WAV file
+/// inside `dir`, returning the path written.
+fn save_utterance_wav(dir: &Path, sample_rate: u32, samples: &[f32]) -> PathBuf {
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S%3f");
+    let path = dir.join(format!("utterance_{}.wav", timestamp));
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).expect("Failed to create utterance WAV");
+    for &sample in samples {
+        writer.write_sample(sample).unwrap();
+    }
+    writer.finalize().expect("Failed to finalize utterance WAV");
+    path
+}
+
+/// A recorded mute/unmute transition, timestamped so a metadata consumer can
+/// line it up against the primary take.
+#[derive(Serialize, Debug)]
+struct MuteTransition {
+    timestamp: chrono::DateTime<Local>,
+    muted: bool,
+}
+
+/// Self-describing record of a single take: device, format, the muted-sample
+/// calibration amplitude it was judged against, and every mute/unmute
+/// transition observed during capture. Written as a JSON sidecar in the
+/// session's recordings directory (next to the primary recording unless
+/// `--output` or the config's `output` put it elsewhere) so later batch
+/// transcription can query takes without
+/// re-opening the audio itself.
+#[derive(Serialize, Debug)]
+struct RecordingMetadata {
+    id: Uuid,
+    device_name: String,
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+    start_time: chrono::DateTime<Local>,
+    muted_sample_amplitude: f64,
+    mute_transitions: Vec<MuteTransition>,
+}
+
+/// A single take's on-disk identity: a v4 UUID under the project data dir,
+/// so every recording is provenance-tracked instead of overwriting a
+/// hardcoded `output.wav`.
+struct RecordingSession {
+    id: Uuid,
+    dir: PathBuf,
+}
+
+impl RecordingSession {
+    fn new() -> Self {
+        let dir = ProjectDirs::from("ca", "teamdman", "mic_activity_example")
+            .map(|dirs| dirs.data_dir().join("recordings"))
+            .unwrap_or_else(|| PathBuf::from("recordings"));
+        fs::create_dir_all(&dir).expect("Failed to create recordings directory");
+        RecordingSession {
+            id: Uuid::new_v4(),
+            dir,
+        }
+    }
+
+    fn wav_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.wav", self.id))
+    }
+
+    fn metadata_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.json", self.id))
+    }
+
+    #[cfg(feature = "record-hdf5")]
+    fn hdf5_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.h5", self.id))
+    }
+
+    fn write_metadata(&self, metadata: &RecordingMetadata) {
+        if let Ok(data) = serde_json::to_string_pretty(metadata) {
+            let _ = fs::write(self.metadata_path(), data);
+        }
+    }
+
+    /// Converts the just-finalized WAV into the HDF5 container `lasprs`
+    /// uses: a raw sample dataset alongside the metadata fields as
+    /// attributes. The WAV is removed afterwards since this is a selectable
+    /// backend, not a second copy of the same take.
+    #[cfg(feature = "record-hdf5")]
+    fn write_hdf5(&self, wav_path: &Path, metadata: &RecordingMetadata) {
+        let mut reader =
+            hound::WavReader::open(wav_path).expect("Failed to reopen WAV for HDF5 export");
+        let spec = reader.spec();
+        let samples: Vec<i32> = match spec.sample_format {
+            hound::SampleFormat::Int => reader.samples::<i32>().map(|s| s.unwrap()).collect(),
+            hound::SampleFormat::Float => reader
+                .samples::<f32>()
+                .map(|s| (s.unwrap() * i16::MAX as f32) as i32)
+                .collect(),
+        };
+
+        let file = hdf5::File::create(self.hdf5_path()).expect("Failed to create HDF5 file");
+        file.new_dataset_builder()
+            .with_data(&samples)
+            .create("samples")
+            .expect("Failed to write HDF5 sample dataset");
+        file.new_attr::<u32>()
+            .create("sample_rate")
+            .unwrap()
+            .write_scalar(&metadata.sample_rate)
+            .unwrap();
+        file.new_attr::<u16>()
+            .create("channels")
+            .unwrap()
+            .write_scalar(&metadata.channels)
+            .unwrap();
+        file.new_attr::<u16>()
+            .create("bits_per_sample")
+            .unwrap()
+            .write_scalar(&metadata.bits_per_sample)
+            .unwrap();
+        file.new_attr::<f64>()
+            .create("muted_sample_amplitude")
+            .unwrap()
+            .write_scalar(&metadata.muted_sample_amplitude)
+            .unwrap();
+        drop(file);
+
+        let _ = fs::remove_file(wav_path);
+    }
+}
 
 fn main() {
+    tracing_subscriber::fmt::init();
+
+    let cli = Cli::parse();
+    let file_config = ExampleConfig::load_or_default();
+
     // Get the default host for audio devices
     let host = cpal::default_host();
 
     // Collect all available input devices (microphones)
-    let devices: Vec<_> = host
+    let devices: Vec<Device> = host
         .input_devices()
         .expect("No input devices available")
         .collect();
@@ -22,70 +523,99 @@ fn main() {
         return;
     }
 
-    // List the available input devices
-    println!("Available input devices:");
-    for (i, device) in devices.iter().enumerate() {
-        println!("{}: {}", i, device.name().unwrap_or("Unknown".to_string()));
+    if cli.list_devices {
+        println!("Available input devices:");
+        for (i, device) in devices.iter().enumerate() {
+            println!("{}: {}", i, device.name().unwrap_or("Unknown".to_string()));
+        }
+        return;
     }
 
-    // Prompt the user to select a device
-    print!("Please select an input device by number: ");
-    io::stdout().flush().unwrap(); // Ensure the prompt is displayed
+    // When --device is given, resolve it (by exact name, then by
+    // enumeration index) without touching stdin at all; otherwise fall back
+    // to the original interactive menu.
+    let (device, recording_duration) = if let Some(wanted) = &cli.device {
+        let matched_index = devices
+            .iter()
+            .position(|d| d.name().unwrap_or_default() == *wanted)
+            .or_else(|| wanted.parse::<usize>().ok().filter(|i| *i < devices.len()));
+        let device = match matched_index {
+            Some(index) => devices.into_iter().nth(index).expect("device somehow not present???"),
+            None => {
+                eprintln!("No input device matching '{}'", wanted);
+                return;
+            }
+        };
+        (device, cli.duration.map(Duration::from_secs))
+    } else {
+        // List the available input devices
+        println!("Available input devices:");
+        for (i, device) in devices.iter().enumerate() {
+            println!("{}: {}", i, device.name().unwrap_or("Unknown".to_string()));
+        }
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    let device_index: usize = input.trim().parse().expect("Please enter a valid number");
+        // Prompt the user to select a device
+        print!("Please select an input device by number: ");
+        io::stdout().flush().unwrap(); // Ensure the prompt is displayed
 
-    if device_index >= devices.len() {
-        println!("Invalid device index");
-        return;
-    }
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).unwrap();
+        let device_index: usize = input.trim().parse().expect("Please enter a valid number");
 
-    let device = &devices[device_index];
-    let device_name = device.name().unwrap_or("Unknown".to_string());
+        if device_index >= devices.len() {
+            println!("Invalid device index");
+            return;
+        }
+
+        let device = devices.into_iter().nth(device_index).expect("device somehow not present???");
 
-    // Prompt the user to enter the recording duration in seconds
-    print!("Please enter the recording duration in seconds: ");
-    io::stdout().flush().unwrap();
+        // Prompt the user to enter the recording duration in seconds
+        print!("Please enter the recording duration in seconds: ");
+        io::stdout().flush().unwrap();
 
-    let mut duration_input = String::new();
-    io::stdin().read_line(&mut duration_input).unwrap();
-    let duration_secs: u64 = duration_input
-        .trim()
-        .parse()
-        .expect("Please enter a valid number");
+        let mut duration_input = String::new();
+        io::stdin().read_line(&mut duration_input).unwrap();
+        let duration_secs: u64 = duration_input
+            .trim()
+            .parse()
+            .expect("Please enter a valid number");
+
+        (device, Some(Duration::from_secs(duration_secs)))
+    };
+    let device = &device;
+    let device_name = device.name().unwrap_or("Unknown".to_string());
+    // Every take gets a provenance-tracked home under the project data dir
+    // unless an explicit --output/config path overrides it.
+    let recording_session = RecordingSession::new();
+    let start_time_stamp = Local::now();
+    let output_path = cli
+        .output
+        .clone()
+        .or_else(|| file_config.output.clone())
+        .unwrap_or_else(|| recording_session.wav_path());
+    let muted_samples_dir_path = cli
+        .muted_sample_dir
+        .clone()
+        .unwrap_or(file_config.muted_sample_dir.clone());
+    let resample_16k = cli.resample_16k || file_config.resample_16k;
 
     // Get the default input configuration for the selected device
     let config = device.default_input_config().unwrap();
     println!("Selected device: {}", device_name);
-    println!("Recording for {} seconds...", duration_secs);
+    match recording_duration {
+        Some(duration) => println!("Recording for {} seconds...", duration.as_secs()),
+        None => println!("Recording until stopped..."),
+    }
 
     // Set up recording parameters
     let sample_format = config.sample_format();
     let config: cpal::StreamConfig = config.into();
 
-    let bits_per_sample = match sample_format {
-        cpal::SampleFormat::I16 => 16,
-        cpal::SampleFormat::U16 => 16,
-        cpal::SampleFormat::F32 => 32,
-        _ => 16, // Default to 16 bits per sample
-    };
-
-    let hound_sample_format = match sample_format {
-        cpal::SampleFormat::I16 | cpal::SampleFormat::U16 => hound::SampleFormat::Int,
-        cpal::SampleFormat::F32 => hound::SampleFormat::Float,
-        _ => hound::SampleFormat::Int,
-    };
-
-    let spec = hound::WavSpec {
-        channels: config.channels,
-        sample_rate: config.sample_rate.0,
-        bits_per_sample,
-        sample_format: hound_sample_format,
-    };
+    let spec = capture_wav_spec(config.channels, config.sample_rate.0, sample_format);
+    let bits_per_sample = spec.bits_per_sample;
 
     // Check for existing muted microphone sample
-    let muted_samples_dir = Path::new("muted_microphone_samples");
+    let muted_samples_dir = muted_samples_dir_path.as_path();
     let sanitized_device_name = device_name.replace("/", "_").replace("\\", "_");
     let muted_sample_filename = format!("muted {}.wav", sanitized_device_name);
     let muted_sample_path = muted_samples_dir.join(muted_sample_filename);
@@ -97,13 +627,23 @@ fn main() {
                 .expect("Failed to create directory for muted samples");
         }
 
-        // Prompt user to mute microphone
-        println!(
-            "No muted sample found for '{}'. Please mute your microphone and press Enter to record a 5-second muted sample.",
-            device_name
-        );
-        let mut dummy_input = String::new();
-        io::stdin().read_line(&mut dummy_input).unwrap();
+        // A `--device` invocation is a headless/scripted run, so skip the
+        // "press Enter" wait and just capture the calibration sample
+        // assuming the mic is already muted. The interactive menu path
+        // still waits for confirmation.
+        if cli.device.is_some() {
+            println!(
+                "No muted sample found for '{}'. Recording a 5-second muted sample (assuming it's already muted).",
+                device_name
+            );
+        } else {
+            println!(
+                "No muted sample found for '{}'. Please mute your microphone and press Enter to record a 5-second muted sample.",
+                device_name
+            );
+            let mut dummy_input = String::new();
+            io::stdin().read_line(&mut dummy_input).unwrap();
+        }
 
         // Record 5-second muted sample
         println!("Recording muted sample for 5 seconds...");
@@ -131,145 +671,113 @@ fn main() {
     );
 
     // Create a WAV writer to write the audio data
-    let wav_writer = hound::WavWriter::create("output.wav", spec).unwrap();
+    let wav_writer = hound::WavWriter::create(&output_path, spec).unwrap();
     let writer = Arc::new(Mutex::new(Some(wav_writer))); // Wrap in Option
 
+    // When --resample-16k is passed, mirror the capture into a second,
+    // model-ready 16kHz mono WAV alongside the raw output.
+    let resample_16k_path = output_path.with_file_name(format!(
+        "{}_16k.wav",
+        output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("output")
+    ));
+    let resample16k = if resample_16k {
+        Some(Arc::new(Mutex::new(Resample16k::new(
+            config.sample_rate.0,
+            config.channels as usize,
+            &resample_16k_path,
+        ))))
+    } else {
+        None
+    };
+
     // Shared state for activity level and mute detection
+    let input_channels = config.channels as usize;
     let shared_state = Arc::new(Mutex::new(SharedState {
         activity_level_history: Vec::new(),
         is_muted: false,
         sample_rate: config.sample_rate.0,
+        segmenter: UtteranceSegmenter::new(config.sample_rate.0),
     }));
 
-    let err_fn = |err| eprintln!("An error occurred on the input audio stream: {}", err);
-
-    // Build the input stream based on the sample format
-    let stream = match sample_format {
-        cpal::SampleFormat::F32 => {
-            let writer_clone = writer.clone();
-            let shared_state_clone = shared_state.clone();
-            device.build_input_stream(
-                &config,
-                move |data: &[f32], _: &_| {
-                    write_input_data_f32(data, &writer_clone);
-
-                    // Analyze data to detect mute/unmute
-                    let amplitude = calculate_rms_amplitude_f32(data);
-
-                    // Update shared state
-                    let mut state = shared_state_clone.lock().unwrap();
-                    state.activity_level_history.push(amplitude as f64);
-                    // Keep history for last 5 seconds
-                    let max_history_length = (state.sample_rate as usize / data.len()) * 5;
-                    if state.activity_level_history.len() > max_history_length {
-                        state.activity_level_history.remove(0);
-                    }
-
-                    // Calculate moving average
-                    let sum: f64 = state.activity_level_history.iter().sum();
-                    let moving_average = sum / state.activity_level_history.len() as f64;
-
-                    // Determine mute state
-                    state.is_muted =
-                        moving_average <= muted_sample_amplitude + get_threshold_margin_f32();
-
-                    // For debugging: print current moving average and mute state
-                    // println!(
-                    //     "Moving Average: {:.6}, Muted: {}",
-                    //     moving_average, state.is_muted
-                    // );
-                },
-                err_fn,
-            )
+    // Closed-out utterances are handed off to a worker thread so saving the
+    // clip never blocks the audio callback.
+    let utterances_dir = Path::new("utterances");
+    fs::create_dir_all(utterances_dir).expect("Failed to create utterances directory");
+    let (utterance_tx, utterance_rx) = mpsc::channel::<Vec<f32>>();
+    let utterance_sample_rate = config.sample_rate.0;
+    let utterance_worker = thread::spawn(move || {
+        for utterance in utterance_rx {
+            let duration_secs = utterance.len() as f32 / utterance_sample_rate as f32;
+            let path = save_utterance_wav(utterances_dir, utterance_sample_rate, &utterance);
+            info!(
+                path = %path.display(),
+                duration_secs,
+                "Saved utterance"
+            );
         }
-        cpal::SampleFormat::I16 => {
-            let writer_clone = writer.clone();
-            let shared_state_clone = shared_state.clone();
-            device.build_input_stream(
-                &config,
-                move |data: &[i16], _: &_| {
-                    write_input_data_i16(data, &writer_clone);
-
-                    // Analyze data to detect mute/unmute
-                    let amplitude = calculate_rms_amplitude_i16(data);
-
-                    // Update shared state
-                    let mut state = shared_state_clone.lock().unwrap();
-                    state.activity_level_history.push(amplitude);
-                    // Keep history for last 5 seconds
-                    let max_history_length = (state.sample_rate as usize / data.len()) * 5;
-                    if state.activity_level_history.len() > max_history_length {
-                        state.activity_level_history.remove(0);
-                    }
+    });
 
-                    // Calculate moving average
-                    let sum: f64 = state.activity_level_history.iter().sum();
-                    let moving_average = sum / state.activity_level_history.len() as f64;
-
-                    // Determine mute state
-                    state.is_muted =
-                        moving_average <= muted_sample_amplitude + get_threshold_margin_i16();
-
-                    // For debugging: print current moving average and mute state
-                    // println!(
-                    //     "Moving Average: {:.6}, Muted: {}",
-                    //     moving_average, state.is_muted
-                    // );
-                },
-                err_fn,
-            )
-        }
-        cpal::SampleFormat::U16 => {
-            let writer_clone = writer.clone();
-            let shared_state_clone = shared_state.clone();
-            device.build_input_stream(
-                &config,
-                move |data: &[u16], _: &_| {
-                    write_input_data_u16(data, &writer_clone);
-
-                    // Analyze data to detect mute/unmute
-                    let amplitude = calculate_rms_amplitude_u16(data);
-
-                    // Update shared state
-                    let mut state = shared_state_clone.lock().unwrap();
-                    state.activity_level_history.push(amplitude);
-                    // Keep history for last 5 seconds
-                    let max_history_length = (state.sample_rate as usize / data.len()) * 5;
-                    if state.activity_level_history.len() > max_history_length {
-                        state.activity_level_history.remove(0);
-                    }
-
-                    // Calculate moving average
-                    let sum: f64 = state.activity_level_history.iter().sum();
-                    let moving_average = sum / state.activity_level_history.len() as f64;
-
-                    // Determine mute state
-                    state.is_muted =
-                        moving_average <= muted_sample_amplitude + get_threshold_margin_i16();
-
-                    // For debugging: print current moving average and mute state
-                    // println!(
-                    //     "Moving Average: {:.6}, Muted: {}",
-                    //     moving_average, state.is_muted
-                    // );
-                },
-                err_fn,
-            )
-        }
+    // Build the input stream based on the sample format. One generic
+    // `run_capture` instantiation per format replaces what used to be three
+    // near-identical closures.
+    let stream = match sample_format {
+        cpal::SampleFormat::F32 => run_capture::<f32>(
+            device,
+            &config,
+            writer.clone(),
+            shared_state.clone(),
+            resample16k.clone(),
+            utterance_tx.clone(),
+            muted_sample_amplitude,
+            input_channels,
+        ),
+        cpal::SampleFormat::I16 => run_capture::<i16>(
+            device,
+            &config,
+            writer.clone(),
+            shared_state.clone(),
+            resample16k.clone(),
+            utterance_tx.clone(),
+            muted_sample_amplitude,
+            input_channels,
+        ),
+        cpal::SampleFormat::U16 => run_capture::<u16>(
+            device,
+            &config,
+            writer.clone(),
+            shared_state.clone(),
+            resample16k.clone(),
+            utterance_tx.clone(),
+            muted_sample_amplitude,
+            input_channels,
+        ),
         _ => panic!("Unsupported sample format"),
-    }
-    .expect("Failed to build input stream");
+    };
 
     // Start the input stream
     stream.play().expect("Failed to start input stream");
 
-    // Record audio for the specified duration and monitor mute state
+    // Shared with the Ctrl-C handler so an open-ended capture can be stopped
+    // cleanly, finalizing the WAV on demand instead of being killed outright.
+    let recording = Arc::new(AtomicBool::new(true));
+    {
+        let recording = recording.clone();
+        ctrlc::set_handler(move || {
+            println!("\nReceived Ctrl-C, stopping recording...");
+            recording.store(false, Ordering::SeqCst);
+        })
+        .expect("Failed to set Ctrl-C handler");
+    }
+
+    // Record audio for the specified duration (or indefinitely, until the
+    // process is stopped) and monitor mute state
     let start_time = Instant::now();
     let print_interval = Duration::from_secs(1);
     let mut last_print_time = Instant::now();
     let mut prev_mute_state = None;
+    let mut mute_transitions: Vec<MuteTransition> = Vec::new();
 
-    while Instant::now().duration_since(start_time) < Duration::from_secs(duration_secs) {
+    while recording.load(Ordering::SeqCst) {
         // Only check every print_interval
         if Instant::now().duration_since(last_print_time) >= print_interval {
             let state = shared_state.lock().unwrap();
@@ -279,10 +787,19 @@ fn main() {
                 } else {
                     println!("Microphone has been unmuted");
                 }
+                mute_transitions.push(MuteTransition {
+                    timestamp: Local::now(),
+                    muted: state.is_muted,
+                });
                 prev_mute_state = Some(state.is_muted);
             }
             last_print_time = Instant::now();
         }
+        if let Some(duration) = recording_duration {
+            if Instant::now().duration_since(start_time) >= duration {
+                break;
+            }
+        }
         thread::sleep(Duration::from_millis(100));
     }
 
@@ -295,7 +812,150 @@ fn main() {
         writer.finalize().unwrap();
     }
 
-    println!("Recording saved to output.wav");
+    if let Some(r16k) = resample16k {
+        let mut r16k = Arc::try_unwrap(r16k)
+            .unwrap_or_else(|_| panic!("resampler still shared after stream was dropped"))
+            .into_inner()
+            .unwrap();
+        r16k.flush();
+        r16k.finalize();
+        println!("16kHz mono recording saved to {}", resample_16k_path.display());
+    }
+
+    // Flush whatever utterance was still open when the stream stopped, then
+    // let the worker drain the channel and exit.
+    {
+        let mut state = shared_state.lock().unwrap();
+        if state.segmenter.active {
+            let remainder = std::mem::take(&mut state.segmenter.data_so_far);
+            if !remainder.is_empty() {
+                let _ = utterance_tx.send(remainder);
+            }
+        }
+    }
+    drop(utterance_tx);
+    utterance_worker
+        .join()
+        .expect("Utterance worker thread panicked");
+
+    let metadata = RecordingMetadata {
+        id: recording_session.id,
+        device_name,
+        sample_rate: config.sample_rate.0,
+        channels: config.channels,
+        bits_per_sample,
+        start_time: start_time_stamp,
+        muted_sample_amplitude,
+        mute_transitions,
+    };
+    recording_session.write_metadata(&metadata);
+    #[cfg(feature = "record-hdf5")]
+    recording_session.write_hdf5(&output_path, &metadata);
+
+    // `write_hdf5` deletes the source WAV once the HDF5 container is
+    // written, so with that feature on `output_path` no longer exists by
+    // the time we'd otherwise print it.
+    #[cfg(feature = "record-hdf5")]
+    println!(
+        "Recording saved to {}",
+        recording_session.hdf5_path().display()
+    );
+    #[cfg(not(feature = "record-hdf5"))]
+    println!("Recording saved to {}", output_path.display());
+    println!(
+        "Recording metadata saved to {}",
+        recording_session.metadata_path().display()
+    );
+}
+
+/// Builds the input stream for the main capture path: writes every sample to
+/// the primary WAV, optionally mirrors it into the 16kHz resampler, updates
+/// the mute-detection moving average, and feeds the utterance segmenter.
+/// Generic over `T` so the F32/I16/U16 cpal formats share this one body
+/// instead of each duplicating it.
+fn run_capture<T: CaptureSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    writer: Arc<Mutex<Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>>>,
+    shared_state: Arc<Mutex<SharedState>>,
+    resample16k: Option<Arc<Mutex<Resample16k>>>,
+    utterance_tx: mpsc::Sender<Vec<f32>>,
+    muted_sample_amplitude: f64,
+    input_channels: usize,
+) -> cpal::Stream {
+    let err_fn = |err| eprintln!("An error occurred on the input audio stream: {}", err);
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &_| {
+                {
+                    let mut guard = writer.lock().unwrap();
+                    if let Some(ref mut w) = *guard {
+                        for &sample in data {
+                            sample.write_wav_sample(w).unwrap();
+                        }
+                    }
+                }
+
+                let analysis: Vec<f32> = data.iter().map(|&s| s.to_analysis_f32()).collect();
+
+                if let Some(r16k) = &resample16k {
+                    r16k.lock().unwrap().push(&analysis);
+                }
+
+                // Analyze data to detect mute/unmute
+                let amplitude = rms_amplitude(&analysis);
+
+                // Update shared state
+                let mut state = shared_state.lock().unwrap();
+                state.activity_level_history.push(amplitude);
+                // Keep history for last 5 seconds
+                let max_history_length = (state.sample_rate as usize / data.len()) * 5;
+                if state.activity_level_history.len() > max_history_length {
+                    state.activity_level_history.remove(0);
+                }
+
+                // Calculate moving average
+                let sum: f64 = state.activity_level_history.iter().sum();
+                let moving_average = sum / state.activity_level_history.len() as f64;
+
+                // Determine mute state
+                state.is_muted = moving_average <= muted_sample_amplitude + MUTE_THRESHOLD_MARGIN;
+
+                // Segment the stream into per-utterance clips.
+                let mono = downmix_to_mono_f32(&analysis, input_channels);
+                if let Some(utterance) = state.segmenter.push(&mono, state.is_muted) {
+                    let _ = utterance_tx.send(utterance);
+                }
+            },
+            err_fn,
+        )
+        .expect("Failed to build input stream")
+}
+
+/// Builds a bare input stream that only writes samples to `writer`, with no
+/// analysis. Used for the short muted-sample calibration recording, which
+/// doesn't need RMS tracking or segmentation.
+fn build_passthrough_stream<T: CaptureSample>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    writer: Arc<Mutex<Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>>>,
+) -> cpal::Stream {
+    let err_fn = |err| eprintln!("An error occurred on the input audio stream: {}", err);
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &_| {
+                let mut guard = writer.lock().unwrap();
+                if let Some(ref mut w) = *guard {
+                    for &sample in data {
+                        sample.write_wav_sample(w).unwrap();
+                    }
+                }
+            },
+            err_fn,
+        )
+        .expect("Failed to build input stream")
 }
 
 // Function to record a sample for a specified duration
@@ -306,61 +966,16 @@ fn record_sample(
     output_path: &Path,
     duration: Duration,
 ) {
-    let spec = hound::WavSpec {
-        channels: config.channels,
-        sample_rate: config.sample_rate.0,
-        bits_per_sample: match sample_format {
-            cpal::SampleFormat::I16 => 16,
-            cpal::SampleFormat::U16 => 16,
-            cpal::SampleFormat::F32 => 32,
-            _ => 16,
-        },
-        sample_format: match sample_format {
-            cpal::SampleFormat::I16 | cpal::SampleFormat::U16 => hound::SampleFormat::Int,
-            cpal::SampleFormat::F32 => hound::SampleFormat::Float,
-            _ => hound::SampleFormat::Int,
-        },
-    };
-
+    let spec = capture_wav_spec(config.channels, config.sample_rate.0, sample_format);
     let wav_writer = hound::WavWriter::create(output_path, spec).unwrap();
     let writer = Arc::new(Mutex::new(Some(wav_writer))); // Wrap in Option
 
-    let err_fn = |err| eprintln!("An error occurred on the input audio stream: {}", err);
-
     let stream = match sample_format {
-        cpal::SampleFormat::F32 => {
-            let writer_clone = writer.clone();
-            device.build_input_stream(
-                config,
-                move |data: &[f32], _: &_| {
-                    write_input_data_f32(data, &writer_clone);
-                },
-                err_fn,
-            )
-        }
-        cpal::SampleFormat::I16 => {
-            let writer_clone = writer.clone();
-            device.build_input_stream(
-                config,
-                move |data: &[i16], _: &_| {
-                    write_input_data_i16(data, &writer_clone);
-                },
-                err_fn,
-            )
-        }
-        cpal::SampleFormat::U16 => {
-            let writer_clone = writer.clone();
-            device.build_input_stream(
-                config,
-                move |data: &[u16], _: &_| {
-                    write_input_data_u16(data, &writer_clone);
-                },
-                err_fn,
-            )
-        }
+        cpal::SampleFormat::F32 => build_passthrough_stream::<f32>(device, config, writer.clone()),
+        cpal::SampleFormat::I16 => build_passthrough_stream::<i16>(device, config, writer.clone()),
+        cpal::SampleFormat::U16 => build_passthrough_stream::<u16>(device, config, writer.clone()),
         _ => panic!("Unsupported sample format"),
-    }
-    .expect("Failed to build input stream");
+    };
 
     stream.play().expect("Failed to start input stream");
 
@@ -375,20 +990,27 @@ fn record_sample(
     }
 }
 
-// Function to calculate the amplitude of the muted sample
+// Function to calculate the amplitude of the muted sample, normalized the
+// same way `CaptureSample::to_analysis_f32` normalizes live capture data so
+// it's directly comparable against `MUTE_THRESHOLD_MARGIN` regardless of
+// which format the calibration sample happened to be recorded in.
 fn calculate_muted_sample_amplitude(muted_sample_path: &Path) -> f64 {
     let mut reader =
         hound::WavReader::open(muted_sample_path).expect("Failed to open muted sample");
     let spec = reader.spec();
 
-    let samples: Vec<f64> = match spec.sample_format {
-        hound::SampleFormat::Int => reader.samples::<i16>().map(|s| s.unwrap() as f64).collect(),
-        hound::SampleFormat::Float => reader.samples::<f32>().map(|s| s.unwrap() as f64).collect(),
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Int => reader
+            .samples::<i16>()
+            .map(|s| s.unwrap().to_analysis_f32())
+            .collect(),
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .map(|s| s.unwrap().to_analysis_f32())
+            .collect(),
     };
 
-    let sum_squares: f64 = samples.iter().map(|&sample| sample * sample).sum();
-    let rms = (sum_squares / samples.len() as f64).sqrt();
-    rms
+    rms_amplitude(&samples)
 }
 
 // Shared state structure
@@ -396,83 +1018,5 @@ struct SharedState {
     activity_level_history: Vec<f64>,
     is_muted: bool,
     sample_rate: u32,
-}
-
-// Function to write f32 samples to the WAV file
-fn write_input_data_f32(
-    input: &[f32],
-    writer: &Arc<Mutex<Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>>>,
-) {
-    let mut guard = writer.lock().unwrap();
-    if let Some(ref mut writer) = *guard {
-        for &sample in input.iter() {
-            writer.write_sample(sample).unwrap();
-        }
-    }
-}
-
-// Function to write i16 samples to the WAV file
-fn write_input_data_i16(
-    input: &[i16],
-    writer: &Arc<Mutex<Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>>>,
-) {
-    let mut guard = writer.lock().unwrap();
-    if let Some(ref mut writer) = *guard {
-        for &sample in input.iter() {
-            writer.write_sample(sample).unwrap();
-        }
-    }
-}
-
-// Function to write u16 samples to the WAV file (converted to i16)
-fn write_input_data_u16(
-    input: &[u16],
-    writer: &Arc<Mutex<Option<hound::WavWriter<std::io::BufWriter<std::fs::File>>>>>,
-) {
-    let mut guard = writer.lock().unwrap();
-    if let Some(ref mut writer) = *guard {
-        for &sample in input.iter() {
-            // Convert u16 to i16 by subtracting 32768 using i32 to avoid overflow
-            let sample_i16 = (sample as i32 - 32768) as i16;
-            writer.write_sample(sample_i16).unwrap();
-        }
-    }
-}
-
-// Function to calculate RMS amplitude for f32 samples
-fn calculate_rms_amplitude_f32(data: &[f32]) -> f32 {
-    let sum_squares: f32 = data.iter().map(|&sample| sample * sample).sum();
-    let rms = (sum_squares / data.len() as f32).sqrt();
-    rms
-}
-
-// Function to calculate RMS amplitude for i16 samples
-fn calculate_rms_amplitude_i16(data: &[i16]) -> f64 {
-    let sum_squares: f64 = data.iter().map(|&sample| (sample as f64).powi(2)).sum();
-    let rms = (sum_squares / data.len() as f64).sqrt();
-    rms
-}
-
-// Function to calculate RMS amplitude for u16 samples
-fn calculate_rms_amplitude_u16(data: &[u16]) -> f64 {
-    let sum_squares: f64 = data
-        .iter()
-        .map(|&sample| {
-            let sample_i32 = sample as i32 - 32768;
-            (sample_i32 as f64).powi(2)
-        })
-        .sum();
-    let rms = (sum_squares / data.len() as f64).sqrt();
-    rms
-}
-
-// Functions to get threshold margins
-fn get_threshold_margin_f32() -> f64 {
-    // Adjust this margin based on experimentation
-    0.001
-}
-
-fn get_threshold_margin_i16() -> f64 {
-    // Adjust this margin based on experimentation
-    50.0
+    segmenter: UtteranceSegmenter,
 }
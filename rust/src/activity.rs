@@ -0,0 +1,105 @@
+// activity.rs
+//
+// A typed event log for everything the app does, replacing the old
+// free-text `activity_log: Vec<String>`. Events are broadcast so the UI can
+// render a live summary while a background task persists every event as a
+// JSON line in a rotating per-day audit file, giving users a
+// machine-parseable history instead of just free-text lines.
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum ActivityEvent {
+    MicStateChanged {
+        mic: String,
+        from: String,
+        to: String,
+    },
+    TranscriptionHeard {
+        mic: String,
+        text: String,
+    },
+    LightCommandSent {
+        light_id: u32,
+        body: serde_json::Value,
+    },
+    HueAuth {
+        state: String,
+    },
+    CallbackError {
+        name: String,
+        error: String,
+    },
+    /// Catch-all for diagnostics that don't warrant their own variant.
+    Message {
+        text: String,
+    },
+}
+
+impl ActivityEvent {
+    /// One-line human-readable rendering for the Activity Log widget.
+    pub fn summary(&self) -> String {
+        match self {
+            ActivityEvent::MicStateChanged { mic, from, to } => {
+                format!("{mic}: {from} -> {to}")
+            }
+            ActivityEvent::TranscriptionHeard { mic, text } => format!("[{mic}] Heard \"{text}\""),
+            ActivityEvent::LightCommandSent { light_id, body } => {
+                format!("Light {light_id} command: {body}")
+            }
+            ActivityEvent::HueAuth { state } => format!("Hue auth: {state}"),
+            ActivityEvent::CallbackError { name, error } => {
+                format!("Callback {name} failed: {error}")
+            }
+            ActivityEvent::Message { text } => text.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    timestamp: DateTime<Local>,
+    #[serde(flatten)]
+    event: &'a ActivityEvent,
+}
+
+/// Appends `event` as one JSON line to the audit file for the current day
+/// under `dir`, creating both as needed.
+fn append_to_audit_log(dir: &Path, event: &ActivityEvent) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.jsonl", Local::now().format("%Y-%m-%d")));
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let record = AuditRecord {
+        timestamp: Local::now(),
+        event,
+    };
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+    Ok(())
+}
+
+/// Background task: drains the broadcast channel and persists every event,
+/// independent of whatever the UI is doing with its own subscription.
+pub async fn run_audit_writer(mut events: broadcast::Receiver<ActivityEvent>, dir: std::path::PathBuf) {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if let Err(e) = append_to_audit_log(&dir, &event) {
+                    error!("Failed to append activity event to audit log: {}", e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                warn!("Audit writer lagged behind activity log, dropped {} events", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
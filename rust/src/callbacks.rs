@@ -0,0 +1,121 @@
+// callbacks.rs
+//
+// Pluggable transcription callbacks: anything that wants to react to a
+// freshly transcribed segment implements `TranscriptionCallback` and is
+// registered in `AppState::transcription_callbacks`, instead of being
+// special-cased by name in `handle_transcription_result`.
+
+use crate::intents::{dispatch_transcript_to_intents, RegisteredIntentHandler};
+use crate::transcription::{save_transcription_result, TranscriptionResult};
+use crate::ui::AppState;
+use async_trait::async_trait;
+use chrono::Local;
+use reqwest::Client;
+use tracing::error;
+
+#[async_trait]
+pub trait TranscriptionCallback: Send + Sync {
+    /// Human-readable name shown in the `Callbacks` tab bar.
+    fn name(&self) -> &str;
+    async fn on_transcription(
+        &self,
+        app_state: &AppState,
+        result: &TranscriptionResult,
+    ) -> anyhow::Result<()>;
+}
+
+/// A registered callback plus whether it's currently switched on. Callbacks
+/// stay registered while disabled so the toggle hotkey can flip them back on
+/// without re-registering anything.
+pub struct RegisteredCallback {
+    pub callback: Box<dyn TranscriptionCallback>,
+    pub enabled: bool,
+}
+
+impl RegisteredCallback {
+    pub fn enabled(callback: Box<dyn TranscriptionCallback>) -> Self {
+        RegisteredCallback {
+            callback,
+            enabled: true,
+        }
+    }
+
+    pub fn disabled(callback: Box<dyn TranscriptionCallback>) -> Self {
+        RegisteredCallback {
+            callback,
+            enabled: false,
+        }
+    }
+}
+
+/// Appends every segment to the rotating JSONL/SRT/VTT transcript files.
+pub struct WriteJsonLine;
+
+#[async_trait]
+impl TranscriptionCallback for WriteJsonLine {
+    fn name(&self) -> &str {
+        "WriteJsonLine"
+    }
+
+    async fn on_transcription(
+        &self,
+        app_state: &AppState,
+        result: &TranscriptionResult,
+    ) -> anyhow::Result<()> {
+        save_transcription_result(
+            &app_state.config,
+            result,
+            result.captured_at.unwrap_or_else(Local::now),
+        )
+    }
+}
+
+/// Runs each segment through the pluggable intent subsystem (Hue light
+/// control and whatever else is registered in `handlers`).
+pub struct ChatLights {
+    pub handlers: Vec<RegisteredIntentHandler>,
+}
+
+#[async_trait]
+impl TranscriptionCallback for ChatLights {
+    fn name(&self) -> &str {
+        "ChatLights"
+    }
+
+    async fn on_transcription(
+        &self,
+        app_state: &AppState,
+        result: &TranscriptionResult,
+    ) -> anyhow::Result<()> {
+        for segment in &result.segments {
+            dispatch_transcript_to_intents(app_state, &self.handlers, &segment.text).await?;
+        }
+        Ok(())
+    }
+}
+
+/// POSTs each segment's JSON to a configured webhook URL.
+pub struct Webhook {
+    pub url: String,
+}
+
+#[async_trait]
+impl TranscriptionCallback for Webhook {
+    fn name(&self) -> &str {
+        "Webhook"
+    }
+
+    async fn on_transcription(
+        &self,
+        _app_state: &AppState,
+        result: &TranscriptionResult,
+    ) -> anyhow::Result<()> {
+        let client = Client::new();
+        for segment in &result.segments {
+            if let Err(e) = client.post(&self.url).json(segment).send().await {
+                error!("Webhook callback failed to post to {}: {}", self.url, e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -15,8 +15,76 @@ pub struct AppConfig {
     pub logs_editor: String,
     pub transcription_api_url: String,
     pub transcription_results_dir: PathBuf,
+    pub transcript_format: TranscriptFormat,
+    /// Address the local transcription HTTP+SSE server binds to.
+    pub serve_address: String,
+    /// When set, the built-in `Webhook` transcription callback POSTs each
+    /// segment's JSON to this URL.
+    pub webhook_url: Option<String>,
+    /// Bot token used for the optional Discord voice/text integration.
+    pub discord_token: Option<String>,
+    pub discord_guild_id: Option<u64>,
+    /// Voice channel to join as an audio source when `discord_token` is set.
+    pub discord_voice_channel_id: Option<u64>,
+    /// Text channel finished transcripts are posted back to.
+    pub discord_text_channel_id: Option<u64>,
     pub key_config: KeyConfig,
     pub microphones: HashMap<String, MicrophoneConfig>,
+    /// Per-device overrides for sample rate/format/channel count and buffer
+    /// size. For a matching device the stream config is picked from
+    /// `device.supported_input_configs()` instead of
+    /// `device.default_input_config()` when hooking microphones. Checked in
+    /// order; the first matching entry wins.
+    pub audio_devices: Vec<CustomAudioDeviceConfig>,
+    /// Chat-completion endpoint the pluggable intent subsystem calls to turn
+    /// a transcript into tool calls. Expected to speak an Ollama/OpenAI-style
+    /// streaming `/api/chat` format.
+    pub intent_model_url: String,
+    /// Model name passed in each intent-dispatch request.
+    pub intent_model: String,
+    /// Whether the built-in Hue `set_light_state` intent handler is
+    /// registered. Other intent handlers get their own flag alongside it.
+    pub hue_intent_enabled: bool,
+    /// Minimum token-overlap (Jaccard) similarity a light or group name must
+    /// score against the transcribed name before `resolve_targets` accepts
+    /// it as a match, rather than treating it as unrelated noise.
+    pub hue_fuzzy_match_threshold: f64,
+    /// IP/hostname of the Hue bridge on the local network. Empty until the
+    /// user fills it in; `authenticate_lights` refuses to run without it.
+    pub hue_bridge_ip: String,
+    /// Bridge username minted by `authenticate_lights` once the bridge's
+    /// link button has been pressed. `None` until authenticated.
+    pub hue_username: Option<String>,
+    /// Spoken phrase that must be heard before `Commands::Listen` opens a
+    /// full dictation session. `None` means wake-word mode can't be used.
+    pub wake_word: Option<String>,
+    /// Optional phonetic spelling of `wake_word`, matched via a coarse
+    /// Soundex comparison against each word the recognizer heard. More
+    /// forgiving of recognizer misspellings than `wake_word`'s exact
+    /// substring match, for unusual trigger phrases.
+    pub wake_phonetic: Option<String>,
+    /// Seconds of silence that end a candidate utterance in wake-word mode,
+    /// after which it is transcribed and checked for the wake word. Once the
+    /// wake word is heard the mic switches to ordinary voice-activity
+    /// capture, whose utterances end on its fixed 1-second pause instead.
+    pub wake_silence_timeout_secs: u32,
+}
+
+/// Output format for saved transcription results.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    /// One JSON-encoded `TranscriptionResult` per line (the original format).
+    Jsonl,
+    /// SubRip subtitle cues.
+    Srt,
+    /// WebVTT subtitle cues.
+    Vtt,
+}
+
+impl Default for TranscriptFormat {
+    fn default() -> Self {
+        TranscriptFormat::Jsonl
+    }
 }
 
 impl Default for AppConfig {
@@ -30,12 +98,84 @@ impl Default for AppConfig {
                 .map(|x| x.data_dir().join("transcripts"))
                 .ok()
                 .unwrap_or_else(|| PathBuf::from("./transcripts")),
+            transcript_format: TranscriptFormat::default(),
+            serve_address: "127.0.0.1:8000".to_string(),
+            webhook_url: None,
+            discord_token: None,
+            discord_guild_id: None,
+            discord_voice_channel_id: None,
+            discord_text_channel_id: None,
             key_config: KeyConfig::default(),
             microphones: HashMap::new(),
+            audio_devices: Vec::new(),
+            intent_model_url: "http://localhost:11434/api/chat".to_string(),
+            intent_model: "x/llama3.2-vision".to_string(),
+            hue_intent_enabled: true,
+            hue_fuzzy_match_threshold: 0.5,
+            hue_bridge_ip: String::new(),
+            hue_username: None,
+            wake_word: None,
+            wake_phonetic: None,
+            wake_silence_timeout_secs: 2,
+        }
+    }
+}
+
+/// Selects which physical input device a `CustomAudioDeviceConfig` applies
+/// to.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AudioDeviceMatcher {
+    /// Device name matches exactly.
+    ExactName(String),
+    /// Device name contains this substring.
+    NameContains(String),
+    /// Device's position in the host's input-device enumeration order.
+    /// Fragile if devices are plugged/unplugged, but useful when a device's
+    /// reported name isn't stable across runs.
+    Index(usize),
+}
+
+impl AudioDeviceMatcher {
+    pub fn matches(&self, name: &str, index: usize) -> bool {
+        match self {
+            AudioDeviceMatcher::ExactName(expected) => expected == name,
+            AudioDeviceMatcher::NameContains(needle) => name.contains(needle.as_str()),
+            AudioDeviceMatcher::Index(expected) => *expected == index,
         }
     }
 }
 
+/// Sample format requested for a `CustomAudioDeviceConfig`, mirroring the
+/// formats `hook_microphone` knows how to capture.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomSampleFormat {
+    F32,
+    I16,
+    U16,
+}
+
+/// Overrides `hook_microphone` applies instead of blindly trusting
+/// `device.default_input_config()`. Fields left as `None` fall back to
+/// whatever the closest matched supported config (or the device default)
+/// already provides.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CustomAudioDeviceConfig {
+    pub matcher: AudioDeviceMatcher,
+    /// Preferred sample rate; clamped to the closest supported rate if the
+    /// device can't produce it exactly.
+    pub sample_rate: Option<u32>,
+    pub sample_format: Option<CustomSampleFormat>,
+    pub channels: Option<u16>,
+    pub buffering: Option<AudioBufferingConfig>,
+}
+
+/// Explicit cpal buffer size, letting users on high-latency hosts trade
+/// latency for stability instead of trusting cpal's default buffering.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct AudioBufferingConfig {
+    pub frames: u32,
+}
+
 impl AppConfig {
     pub fn load(path: &PathBuf) -> anyhow::Result<Self> {
         if !path.exists() {
@@ -85,6 +225,18 @@ pub struct MicrophoneConfig {
     pub samples_until_idle: u32,
     pub activity_threshold_amplitude: f32,
     pub enabled: bool,
+    /// Silences this mic's audio before it reaches voice-activity detection.
+    pub muted: bool,
+    /// Gain, 0-100, applied in software by scaling samples; the device's own
+    /// volume control is not touched.
+    pub gain: u8,
+    /// When set, this mic starts in `WaitingForPushToTalk` instead of
+    /// `WaitingForVoiceActivity`: audio is only captured while this key
+    /// (plus `push_to_talk_modifier`, if any) is held down.
+    pub push_to_talk_key: Option<char>,
+    /// Modifier that must be held alongside `push_to_talk_key`. `None` means
+    /// the bare key with no modifier.
+    pub push_to_talk_modifier: Option<PttModifier>,
 }
 
 impl Default for MicrophoneConfig {
@@ -93,18 +245,40 @@ impl Default for MicrophoneConfig {
             samples_until_idle: 44100, // 1 second at 44.1kHz
             activity_threshold_amplitude: 0.01,
             enabled: true,
+            muted: false,
+            gain: 100,
+            push_to_talk_key: None,
+            push_to_talk_modifier: None,
         }
     }
 }
 
+/// Modifier key paired with `MicrophoneConfig::push_to_talk_key`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PttModifier {
+    Shift,
+    Control,
+    Alt,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KeyConfig {
     pub quit: char,
     pub help: char,
+    /// Toggles whether the selected microphone is enabled.
     pub mic_toggle_disabled: char,
+    /// Moves the selected microphone in the `Microphones` list.
     pub mic_cycle_mode: char,
-    pub callback_toggle_write: char,
-    pub callback_toggle_typewriter: char,
+    /// Toggles whether the selected microphone is muted.
+    pub mic_mute_toggle: char,
+    /// Raises the selected microphone's software gain.
+    pub mic_gain_up: char,
+    /// Lowers the selected microphone's software gain.
+    pub mic_gain_down: char,
+    /// Moves the selected tab in the `Callbacks` bar.
+    pub callback_cycle_selection: char,
+    /// Toggles the currently selected callback on/off.
+    pub callback_toggle_selected: char,
     pub edit_config: char,
     pub open_config: char,
     pub open_logs: char,
@@ -117,8 +291,11 @@ impl Default for KeyConfig {
             help: 'h',
             mic_toggle_disabled: 'd',
             mic_cycle_mode: 'm',
-            callback_toggle_write: 'w',
-            callback_toggle_typewriter: 't',
+            mic_mute_toggle: 'u',
+            mic_gain_up: '=',
+            mic_gain_down: '-',
+            callback_cycle_selection: 't',
+            callback_toggle_selected: 'w',
             edit_config: 'e',
             open_config: 'b',
             open_logs: 'b',
@@ -0,0 +1,134 @@
+// discord.rs
+//
+// Optional Discord integration: joins a configured voice channel, treats
+// each active speaker as a `Microphone` feeding the existing transcription
+// pipeline (keyed by their SSRC, the same way a local device is keyed by
+// name), and posts finished transcripts back to a text channel.
+
+use crate::callbacks::TranscriptionCallback;
+use crate::microphone::AudioChunk;
+use crate::transcription::TranscriptionResult;
+use crate::ui::AppState;
+use anyhow::{Context, Result};
+use chrono::Local;
+use cpal::{ChannelCount, SampleRate};
+use serenity::all::{ChannelId, GatewayIntents, GuildId};
+use serenity::async_trait;
+use serenity::client::{Client, EventHandler};
+use songbird::{CoreEvent, EventContext, SerenityInit};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{error, info};
+
+/// Discord voice payloads are always decoded to 48kHz stereo PCM.
+const DISCORD_SAMPLE_RATE: SampleRate = SampleRate(48_000);
+const DISCORD_CHANNELS: ChannelCount = 2;
+
+struct Handler;
+
+#[async_trait]
+impl EventHandler for Handler {}
+
+/// Joins `voice_channel_id` in `guild_id` and wires the songbird voice
+/// receiver so every active speaker's decoded PCM is pushed into
+/// `raw_audio_sender` as an `AudioChunk`, exactly like a local `Microphone`.
+/// Runs the gateway connection until the process exits.
+pub async fn join_voice_channel(
+    token: &str,
+    guild_id: u64,
+    voice_channel_id: u64,
+    raw_audio_sender: UnboundedSender<AudioChunk>,
+) -> Result<()> {
+    let intents = GatewayIntents::GUILDS | GatewayIntents::GUILD_VOICE_STATES;
+    let mut client = Client::builder(token, intents)
+        .event_handler(Handler)
+        .register_songbird()
+        .await
+        .context("Failed to build Discord client")?;
+
+    let songbird = songbird::get(&client)
+        .await
+        .context("Songbird not initialized for this client")?;
+
+    let (handler_lock, join_result) = songbird
+        .join(GuildId::new(guild_id), ChannelId::new(voice_channel_id))
+        .await;
+    join_result.context("Failed to join Discord voice channel")?;
+
+    handler_lock.lock().await.add_global_event(
+        CoreEvent::VoicePacket.into(),
+        DiscordVoiceReceiver { raw_audio_sender },
+    );
+
+    info!(
+        "Joined Discord voice channel {} in guild {}",
+        voice_channel_id, guild_id
+    );
+
+    client
+        .start()
+        .await
+        .context("Discord client stopped unexpectedly")
+}
+
+/// Forwards each speaker's decoded PCM into the app's normal audio pipeline.
+struct DiscordVoiceReceiver {
+    raw_audio_sender: UnboundedSender<AudioChunk>,
+}
+
+#[async_trait]
+impl songbird::EventHandler for DiscordVoiceReceiver {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<songbird::Event> {
+        if let EventContext::VoicePacket(data) = ctx {
+            if let Some(audio) = data.audio {
+                let mic_name = format!("discord-{}", data.packet.ssrc);
+                let samples: Vec<f32> =
+                    audio.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
+                let chunk = AudioChunk {
+                    mic_name,
+                    channels: DISCORD_CHANNELS,
+                    sample_rate: DISCORD_SAMPLE_RATE,
+                    data: samples,
+                    // songbird hands us decoded PCM with no cpal-style
+                    // callback timing, so there's no stream clock to derive
+                    // these from; gap detection is a no-op for Discord mics.
+                    capture_time: std::time::Duration::ZERO,
+                    sample_index: 0,
+                    captured_at: Local::now(),
+                };
+                if let Err(e) = self.raw_audio_sender.send(chunk) {
+                    error!("Failed to forward Discord voice packet: {}", e);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Posts each finished transcript segment back to a Discord text channel.
+pub struct DiscordTranscriptCallback {
+    pub token: String,
+    pub text_channel_id: u64,
+}
+
+#[async_trait::async_trait]
+impl TranscriptionCallback for DiscordTranscriptCallback {
+    fn name(&self) -> &str {
+        "Discord"
+    }
+
+    async fn on_transcription(
+        &self,
+        _app_state: &AppState,
+        result: &TranscriptionResult,
+    ) -> Result<()> {
+        let http = serenity::http::Http::new(&self.token);
+        let channel = ChannelId::new(self.text_channel_id);
+        for segment in &result.segments {
+            if let Err(e) = channel.say(&http, &segment.text).await {
+                error!("Failed to post transcript to Discord: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
@@ -1,246 +1,678 @@
-
-use crate::app_state::AppState;
-use crate::config::AppConfig;
-use crate::get_config_path;
-use crate::logging::get_logs_path;
-use crate::microphone::{
-    hook_microphones, process_raw_audio, AudioChunk, Microphone, MicrophoneState,
-};
-use crate::transcription::{
-    save_transcription_result, send_audio_for_transcription, TranscriptionResult,
-};
-use anyhow::Context;
-use crossterm::event::{Event as CEvent, EventStream, KeyCode, KeyEvent, KeyEventKind};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-use futures::{FutureExt, StreamExt};
-use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Constraint, Direction, Layout};
-use ratatui::style::{Color, Style};
-use ratatui::text::Span;
-use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Tabs};
-use ratatui::{DefaultTerminal, Frame, Terminal};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io;
-use std::path::PathBuf;
-use std::time::Duration;
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
-use tracing::{debug, error, info, warn};
-
-pub enum HueAuthState {
-    Unauthenticated,
-    AwaitingButtonPress,
-    Authenticated,
-}
-
-
-async fn handle_hue_llm_voice_commands(app_state: &AppState, transcript: &str) -> anyhow::Result<()> {
-    info!("Processing ChatLights for \"{}\"", transcript);
-    let client = Client::new();
-    let model_api_url = "http://localhost:11434/api/generate"; // TODO: make config variable
-    let model = "x/llama3.2-vision"; // TODO: make config variable
-
-    // Build the prompt
-    let prompt = format!(
-        r#"
-You are a light controlling robot.
-Your job is to detect when a user is instructing you to change the lights.
-
-{}
-
-
-Your output should have the following structure.
-{{
-    "light_updates": [ {{
-        "light_id": number,
-        "red": number (0-255),
-        "green": number (0-255),
-        "blue": number (0-255),
-        "brightness": number (1-254),
-        "on": bool
-    }} ]
-}}
-
-If it seems like the user is not talking to the robot, then an empty array should be returned for the "light_updates" property.
-
-Transcript:
-"{}"
-
-Respond only with the JSON output.
-"#,
-        app_state.light_list().await, // We'll implement this method
-        transcript
-    );
-
-    // Send the request
-    let response = client
-        .post(model_api_url)
-        .json(&serde_json::json!({
-            "model": model,
-            "prompt": prompt,
-            "stream": false,
-        }))
-        .send().await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-    let generated_text = response_json
-        .get("response")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("No response from model"))?;
-
-    // Parse the generated text as JSON
-    let light_updates: LightUpdateResponse = serde_json::from_str(generated_text.trim())
-        .context("Failed to parse model response as JSON")?;
-
-    // Process the light updates
-    if !light_updates.light_updates.is_empty() {
-        // Send commands to the Hue bridge
-        for update in light_updates.light_updates {
-            send_hue_command(app_state, update).await?;
-        }
-    }
-
-    Ok(())
-}
-
-async fn send_hue_command(app_state: &AppState, update: LightUpdate) -> anyhow::Result<()> {
-    let bridge_ip = &app_state.config.hue_bridge_ip;
-    let username = match &app_state.config.hue_username {
-        Some(u) => u,
-        None => {
-            app_state.push_activity_log("Not authenticated with Hue bridge.".to_string());
-            return Ok(());
-        }
-    };
-
-    let url = format!(
-        "https://{}/api/{}/lights/{}/state",
-        bridge_ip, username, update.light_id
-    );
-
-    let mut body = serde_json::Map::new();
-
-    if let Some(on) = update.on {
-        body.insert("on".to_string(), serde_json::Value::Bool(on));
-    }
-
-    if let (Some(red), Some(green), Some(blue)) = (update.red, update.green, update.blue) {
-        let (hue, sat, bri) = rgb_to_hsv(red, green, blue);
-        body.insert("hue".to_string(), serde_json::Value::Number(hue.into()));
-        body.insert("sat".to_string(), serde_json::Value::Number(sat.into()));
-        body.insert("bri".to_string(), serde_json::Value::Number(bri.into()));
-    } else if let Some(bri) = update.brightness {
-        body.insert("bri".to_string(), serde_json::Value::Number(bri.into()));
-    }
-
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-
-    let response = client.put(&url).json(&body).send().await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-
-    app_state.push_activity_log(format!(
-        "Sent light command to light {}: {:?}",
-        update.light_id, response_json
-    ));
-
-    Ok(())
-}
-
-
-
-async fn fetch_lights(config: &AppConfig) -> anyhow::Result<HashMap<u32, String>> {
-    let bridge_ip = &config.hue_bridge_ip;
-    let username = match &config.hue_username {
-        Some(u) => u,
-        None => anyhow::bail!("Not authenticated with Hue bridge"),
-    };
-
-    let url = format!("https://{}/api/{}/lights", bridge_ip, username);
-
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-
-    let response = client.get(&url).send().await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-
-    let lights = response_json
-        .as_object()
-        .ok_or_else(|| anyhow::anyhow!("Invalid lights response"))?;
-
-    let mut light_map = HashMap::new();
-
-    for (id_str, light_info) in lights {
-        if let Ok(id) = id_str.parse::<u32>() {
-            if let Some(name) = light_info.get("name").and_then(|n| n.as_str()) {
-                light_map.insert(id, name.to_string());
-            }
-        }
-    }
-
-    Ok(light_map)
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct LightUpdateResponse {
-    light_updates: Vec<LightUpdate>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct LightUpdate {
-    light_id: u32,
-    red: Option<u8>,    // Red value between 0-255
-    green: Option<u8>,  // Green value between 0-255
-    blue: Option<u8>,   // Blue value between 0-255
-    brightness: Option<u8>, // Brightness between 1-254
-    on: Option<bool>,
-}
-fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (u16, u8, u8) {
-    let r = r as f32 / 255.0;
-    let g = g as f32 / 255.0;
-    let b = b as f32 / 255.0;
-
-    let max = r.max(g.max(b));
-    let min = r.min(g.min(b));
-    let delta = max - min;
-
-    // Hue calculation
-    let mut h = 0.0;
-    if delta != 0.0 {
-        if max == r {
-            h = 60.0 * (((g - b) / delta) % 6.0);
-        } else if max == g {
-            h = 60.0 * (((b - r) / delta) + 2.0);
-        } else if max == b {
-            h = 60.0 * (((r - g) / delta) + 4.0);
-        }
-    }
-    if h < 0.0 {
-        h += 360.0;
-    }
-
-    // Saturation calculation
-    let s = if max == 0.0 { 0.0 } else { delta / max };
-
-    // Value calculation
-    let v = max;
-
-    // Map h from [0,360) to [0,65535]
-    let hue = (h / 360.0 * 65535.0).round() as u16;
-
-    // Map s from [0,1] to [0,254]
-    let sat = (s * 254.0).round() as u8;
-
-    // Map v from [0,1] to [1,254]
-    let bri = (v * 253.0 + 1.0).round() as u8;
-
-    (hue, sat, bri)
-}
-
-
-
+// hue.rs
+//
+// Philips Hue bridge integration: link-button authentication, the LLM-driven
+// voice command bridge, and fuzzy light name resolution. Split out of ui.rs
+// so the bridge-specific logic doesn't keep growing alongside the TUI.
+
+use crate::activity::ActivityEvent;
+use crate::config::AppConfig;
+use crate::intents::IntentHandler;
+use crate::ui::AppState;
+use anyhow::Context;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+pub enum HueAuthState {
+    Unauthenticated,
+    AwaitingButtonPress,
+    Authenticated,
+}
+
+pub async fn authenticate_lights(app_state: &mut AppState) -> anyhow::Result<()> {
+    // only proceed if not already authenticated
+    if let HueAuthState::Authenticated { .. } = app_state.hue_auth_state {
+        app_state.push_activity_log("Already authenticated with Hue bridge.");
+        return Ok(());
+    }
+
+    let bridge_ip = &app_state.config.hue_bridge_ip;
+    if bridge_ip.is_empty() {
+        app_state.push_activity_log("Hue bridge IP not set in config.");
+        return Ok(());
+    }
+
+    let url = format!("https://{}/api", bridge_ip);
+
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let response = client
+        .post(&url)
+        .json(&serde_json::json!({"devicetype": "mic_app#rust"}))
+        .send()
+        .await?;
+
+    let response_json: serde_json::Value = response.json().await?;
+
+    let result = response_json
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("Unexpected response"))?;
+
+    if result.is_empty() {
+        anyhow::bail!("Empty response from Hue bridge");
+    }
+
+    let first_item = &result[0];
+
+    if let Some(success) = first_item.get("success") {
+        let username = success
+            .get("username")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("No username in success response"))?
+            .to_string();
+
+        app_state.config.hue_username = Some(username.clone());
+        app_state.hue_auth_state = HueAuthState::Authenticated;
+
+        // Save the config with the new username
+        app_state.config.save(&app_state.config_path)?;
+
+        app_state.push_event(ActivityEvent::HueAuth {
+            state: "authenticated".to_string(),
+        });
+    } else if let Some(error) = first_item.get("error") {
+        let error_type = error.get("type").and_then(|v| v.as_i64()).unwrap_or(0);
+        let description = error
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if error_type == 101 {
+            // link button not pressed
+            app_state.hue_auth_state = HueAuthState::AwaitingButtonPress;
+
+            app_state.push_event(ActivityEvent::HueAuth {
+                state: "awaiting_button_press".to_string(),
+            });
+        } else {
+            app_state.push_activity_log(format!(
+                "Error authenticating with Hue bridge: {}",
+                description
+            ));
+        }
+    } else {
+        app_state.push_activity_log("Unknown response from Hue bridge.");
+    }
+
+    Ok(())
+}
+
+async fn light_list(app_state: &AppState) -> String {
+    match fetch_lights(&app_state.config).await {
+        Ok(lights) => lights
+            .iter()
+            .map(|(id, name)| format!("{}: {}", id, name))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Err(e) => {
+            app_state.push_activity_log(format!("Error fetching lights: {}", e));
+            "".to_string()
+        }
+    }
+}
+
+/// A single tool call's arguments, as the model echoes them back once it
+/// has decided to invoke `set_light_state`. The model is given light names
+/// (not ids) since that's what a transcript will actually contain; the name
+/// is resolved to an id with fuzzy matching in `send_hue_command`.
+#[derive(Serialize, Deserialize, Debug)]
+struct LightUpdate {
+    light_name: String,
+    red: Option<u8>,        // Red value between 0-255
+    green: Option<u8>,      // Green value between 0-255
+    blue: Option<u8>,       // Blue value between 0-255
+    brightness: Option<u8>, // Brightness between 1-254
+    on: Option<bool>,
+}
+
+/// Advertises and executes `set_light_state` through the pluggable intent
+/// subsystem (see `intents.rs`). The only intent handler today; new
+/// voice-controlled devices register their own alongside it in
+/// `AppState::new` without touching the dispatch loop.
+pub struct LightIntentHandler;
+
+#[async_trait]
+impl IntentHandler for LightIntentHandler {
+    fn name(&self) -> &str {
+        "set_light_state"
+    }
+
+    /// Describes `set_light_state` using an OpenAI-style tool schema, so the
+    /// model calls it directly instead of us parsing free-form JSON back out
+    /// of a one-shot completion.
+    fn action_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": "set_light_state",
+                "description": "Change the on/off state, brightness, or color of a single Hue light.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "light_name": { "type": "string", "description": "Name of the light to update, as shown in the light list" },
+                        "red": { "type": "integer", "description": "0-255" },
+                        "green": { "type": "integer", "description": "0-255" },
+                        "blue": { "type": "integer", "description": "0-255" },
+                        "brightness": { "type": "integer", "description": "1-254" },
+                        "on": { "type": "boolean" }
+                    },
+                    "required": ["light_name"]
+                }
+            }
+        })
+    }
+
+    async fn system_context(&self, app_state: &AppState) -> String {
+        light_list(app_state).await
+    }
+
+    async fn execute(&self, app_state: &AppState, arguments: serde_json::Value) -> anyhow::Result<()> {
+        let update: LightUpdate =
+            serde_json::from_value(arguments).context("Invalid set_light_state arguments")?;
+        send_hue_command(app_state, update).await
+    }
+}
+
+/// A Hue "group" (room, zone, or light group) and the lights it contains.
+#[derive(Debug, Clone)]
+pub struct LightGroup {
+    pub name: String,
+    pub light_ids: Vec<u32>,
+}
+
+/// Membership graph between groups and the individual lights they contain,
+/// built from the bridge's `/groups` endpoint, so a command can target
+/// "the kitchen" and fan out to every light in it.
+pub struct LightGroupGraph {
+    pub groups: HashMap<u32, LightGroup>,
+}
+
+impl LightGroupGraph {
+    /// Groups (rooms/zones) that directly contain `light_id`.
+    pub fn groups_containing(&self, light_id: u32) -> Vec<&LightGroup> {
+        self.groups
+            .values()
+            .filter(|group| group.light_ids.contains(&light_id))
+            .collect()
+    }
+}
+
+pub async fn fetch_light_groups(config: &AppConfig) -> anyhow::Result<LightGroupGraph> {
+    let bridge_ip = &config.hue_bridge_ip;
+    let username = match &config.hue_username {
+        Some(u) => u,
+        None => anyhow::bail!("Not authenticated with Hue bridge"),
+    };
+
+    let url = format!("https://{}/api/{}/groups", bridge_ip, username);
+
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let response = client.get(&url).send().await?;
+    let response_json: serde_json::Value = response.json().await?;
+
+    let groups_json = response_json
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Invalid groups response"))?;
+
+    let mut groups = HashMap::new();
+    for (id_str, group_info) in groups_json {
+        let Ok(id) = id_str.parse::<u32>() else {
+            continue;
+        };
+        let Some(name) = group_info.get("name").and_then(|n| n.as_str()) else {
+            continue;
+        };
+        let light_ids = group_info
+            .get("lights")
+            .and_then(|v| v.as_array())
+            .map(|lights| {
+                lights
+                    .iter()
+                    .filter_map(|l| l.as_str())
+                    .filter_map(|l| l.parse::<u32>().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        groups.insert(
+            id,
+            LightGroup {
+                name: name.to_string(),
+                light_ids,
+            },
+        );
+    }
+
+    Ok(LightGroupGraph { groups })
+}
+
+/// Resolves a (possibly mis-transcribed) light or group name to the set of
+/// light ids it should affect, ranking candidates by case-insensitive,
+/// whitespace-tokenized Jaccard similarity (shared tokens / union of
+/// tokens), which ignores word order (e.g. "lamp kitchen" vs "Kitchen
+/// Lamp"). Words split or joined differently ("bed room" vs "bedroom") are
+/// distinct tokens and do not count as shared. Normalized Levenshtein distance only breaks
+/// ties between equally-scored candidates. A group name (e.g. "Kitchen")
+/// expands to every light in that room; ties between a light and its own
+/// group prefer the individual light so "kitchen lamp" doesn't flip the
+/// whole room. A candidate whose score falls below `threshold` is treated
+/// as no match at all, rather than always picking whatever scores highest.
+fn resolve_targets(
+    name: &str,
+    lights: &HashMap<u32, String>,
+    groups: &LightGroupGraph,
+    threshold: f64,
+) -> Vec<u32> {
+    let name = name.to_lowercase();
+
+    let best_light = lights
+        .iter()
+        .map(|(id, candidate)| {
+            let candidate = candidate.to_lowercase();
+            let score = jaccard_similarity(&name, &candidate);
+            let distance = normalized_levenshtein(&name, &candidate);
+            (*id, score, distance)
+        })
+        .fold(None, |best, candidate| Some(better_match(best, candidate)))
+        .filter(|(_, score, _)| *score >= threshold);
+
+    let best_group = groups
+        .groups
+        .values()
+        .map(|group| {
+            let candidate = group.name.to_lowercase();
+            let score = jaccard_similarity(&name, &candidate);
+            let distance = normalized_levenshtein(&name, &candidate);
+            (group, score, distance)
+        })
+        .fold(None, |best, candidate| Some(better_match(best, candidate)))
+        .filter(|(_, score, _)| *score >= threshold);
+
+    match (best_light, best_group) {
+        (Some((light_id, light_score, _)), Some((group, group_score, _)))
+            if group_score > light_score =>
+        {
+            group.light_ids.clone()
+        }
+        (Some((light_id, _, _)), _) => vec![light_id],
+        (None, Some((group, _, _))) => group.light_ids.clone(),
+        (None, None) => Vec::new(),
+    }
+}
+
+/// Picks the higher-scoring of two (candidate, jaccard score, normalized
+/// Levenshtein distance) entries, falling back to the smaller distance to
+/// break a tied score.
+fn better_match<T>(best: Option<(T, f64, f64)>, candidate: (T, f64, f64)) -> (T, f64, f64) {
+    match best {
+        Some(best) if best.1 > candidate.1 => best,
+        Some(best) if best.1 == candidate.1 && best.2 <= candidate.2 => best,
+        _ => candidate,
+    }
+}
+
+/// Case-insensitive, whitespace-tokenized Jaccard similarity: the fraction
+/// of the two names' combined (deduplicated) words they have in common.
+fn jaccard_similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<&str> = a.split_whitespace().collect();
+    let b: HashSet<&str> = b.split_whitespace().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Levenshtein distance scaled to `[0, 1]` by the longer string's length, so
+/// it can break ties between candidates of different lengths on equal
+/// footing.
+fn normalized_levenshtein(a: &str, b: &str) -> f64 {
+    let distance = levenshtein(a, b);
+    let longest = a.chars().count().max(b.chars().count()).max(1);
+    distance as f64 / longest as f64
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut dp = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in dp.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=b.len() {
+        dp[0][j] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            dp[i][j] = (dp[i - 1][j] + 1)
+                .min(dp[i][j - 1] + 1)
+                .min(dp[i - 1][j - 1] + cost);
+        }
+    }
+    dp[a.len()][b.len()]
+}
+
+async fn send_hue_command(app_state: &AppState, update: LightUpdate) -> anyhow::Result<()> {
+    if app_state.config.hue_username.is_none() {
+        app_state.push_activity_log("Not authenticated with Hue bridge.".to_string());
+        return Ok(());
+    }
+
+    let lights = fetch_lights(&app_state.config).await?;
+    let groups = fetch_light_groups(&app_state.config).await?;
+    let light_ids = resolve_targets(
+        &update.light_name,
+        &lights,
+        &groups,
+        app_state.config.hue_fuzzy_match_threshold,
+    );
+    if light_ids.is_empty() {
+        app_state.push_activity_log(format!(
+            "No light or group matched the name \"{}\"",
+            update.light_name
+        ));
+        return Ok(());
+    }
+
+    let mut body = serde_json::Map::new();
+
+    if let Some(on) = update.on {
+        body.insert("on".to_string(), serde_json::Value::Bool(on));
+    }
+
+    if let (Some(red), Some(green), Some(blue)) = (update.red, update.green, update.blue) {
+        let (hue, sat, bri) = rgb_to_hsv(red, green, blue);
+        body.insert("hue".to_string(), serde_json::Value::Number(hue.into()));
+        body.insert("sat".to_string(), serde_json::Value::Number(sat.into()));
+        body.insert("bri".to_string(), serde_json::Value::Number(bri.into()));
+    } else if let Some(bri) = update.brightness {
+        body.insert("bri".to_string(), serde_json::Value::Number(bri.into()));
+    }
+
+    for light_id in light_ids {
+        put_light_state(app_state, light_id, &body).await?;
+    }
+
+    Ok(())
+}
+
+async fn put_light_state(
+    app_state: &AppState,
+    light_id: u32,
+    body: &serde_json::Map<String, serde_json::Value>,
+) -> anyhow::Result<()> {
+    let bridge_ip = &app_state.config.hue_bridge_ip;
+    let username = app_state
+        .config
+        .hue_username
+        .as_ref()
+        .context("Not authenticated with Hue bridge")?;
+
+    let url = format!(
+        "https://{}/api/{}/lights/{}/state",
+        bridge_ip, username, light_id
+    );
+
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let response = client.put(&url).json(body).send().await?;
+
+    let response_json: serde_json::Value = response.json().await?;
+    debug!(
+        "Hue bridge response for light {}: {:?}",
+        light_id, response_json
+    );
+
+    app_state.push_event(ActivityEvent::LightCommandSent {
+        light_id,
+        body: serde_json::Value::Object(body.clone()),
+    });
+
+    Ok(())
+}
+
+fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (u16, u8, u8) {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+
+    let max = r.max(g.max(b));
+    let min = r.min(g.min(b));
+    let delta = max - min;
+
+    // Hue calculation
+    let mut h = 0.0;
+    if delta != 0.0 {
+        if max == r {
+            h = 60.0 * (((g - b) / delta) % 6.0);
+        } else if max == g {
+            h = 60.0 * (((b - r) / delta) + 2.0);
+        } else if max == b {
+            h = 60.0 * (((r - g) / delta) + 4.0);
+        }
+    }
+    if h < 0.0 {
+        h += 360.0;
+    }
+
+    // Saturation calculation
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+
+    // Value calculation
+    let v = max;
+
+    // Map h from [0,360) to [0,65535]
+    let hue = (h / 360.0 * 65535.0).round() as u16;
+
+    // Map s from [0,1] to [0,254]
+    let sat = (s * 254.0).round() as u8;
+
+    // Map v from [0,1] to [1,254]
+    let bri = (v * 253.0 + 1.0).round() as u8;
+
+    (hue, sat, bri)
+}
+
+/// A discovered light's id, name, and current on/off + brightness state,
+/// for the `lights` CLI command's pretty listing.
+pub struct LightDetail {
+    pub id: u32,
+    pub name: String,
+    pub on: bool,
+    pub brightness: u8,
+}
+
+pub async fn fetch_light_details(config: &AppConfig) -> anyhow::Result<Vec<LightDetail>> {
+    let bridge_ip = &config.hue_bridge_ip;
+    let username = match &config.hue_username {
+        Some(u) => u,
+        None => anyhow::bail!("Not authenticated with Hue bridge"),
+    };
+
+    let url = format!("https://{}/api/{}/lights", bridge_ip, username);
+
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let response = client.get(&url).send().await?;
+    let response_json: serde_json::Value = response.json().await?;
+
+    let lights = response_json
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Invalid lights response"))?;
+
+    let mut details: Vec<LightDetail> = lights
+        .iter()
+        .filter_map(|(id_str, info)| {
+            let id = id_str.parse::<u32>().ok()?;
+            let name = info.get("name")?.as_str()?.to_string();
+            let state = info.get("state")?;
+            let on = state.get("on").and_then(|v| v.as_bool()).unwrap_or(false);
+            let brightness = state.get("bri").and_then(|v| v.as_u64()).unwrap_or(0) as u8;
+            Some(LightDetail {
+                id,
+                name,
+                on,
+                brightness,
+            })
+        })
+        .collect();
+    details.sort_by_key(|l| l.id);
+    Ok(details)
+}
+
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_DIM: &str = "\x1b[2m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Prints an aligned, colorized table of discovered lights to stdout, for
+/// the `mic lights` CLI command.
+pub async fn print_lights(config: &AppConfig) -> anyhow::Result<()> {
+    let lights = fetch_light_details(config).await?;
+    if lights.is_empty() {
+        println!("No lights found.");
+        return Ok(());
+    }
+
+    let id_width = lights
+        .iter()
+        .map(|l| l.id.to_string().len())
+        .max()
+        .unwrap_or(2);
+    let name_width = lights.iter().map(|l| l.name.len()).max().unwrap_or(4);
+
+    for light in &lights {
+        let status = if light.on {
+            format!("{ANSI_GREEN}on{ANSI_RESET}  bri {:>3}", light.brightness)
+        } else {
+            format!("{ANSI_DIM}off{ANSI_RESET}")
+        };
+        println!(
+            "{:>id_width$}  {:<name_width$}  {status}",
+            light.id,
+            light.name,
+            id_width = id_width,
+            name_width = name_width,
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn fetch_lights(config: &AppConfig) -> anyhow::Result<HashMap<u32, String>> {
+    let bridge_ip = &config.hue_bridge_ip;
+    let username = match &config.hue_username {
+        Some(u) => u,
+        None => anyhow::bail!("Not authenticated with Hue bridge"),
+    };
+
+    let url = format!("https://{}/api/{}/lights", bridge_ip, username);
+
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+
+    let response = client.get(&url).send().await?;
+
+    let response_json: serde_json::Value = response.json().await?;
+
+    let lights = response_json
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Invalid lights response"))?;
+
+    let mut light_map = HashMap::new();
+
+    for (id_str, light_info) in lights {
+        if let Ok(id) = id_str.parse::<u32>() {
+            if let Some(name) = light_info.get("name").and_then(|n| n.as_str()) {
+                light_map.insert(id, name.to_string());
+            }
+        }
+    }
+
+    Ok(light_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levenshtein_distances() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", "abc"), 0);
+    }
+
+    fn test_graph() -> (HashMap<u32, String>, LightGroupGraph) {
+        let lights = HashMap::from([
+            (1, "Kitchen Lamp".to_string()),
+            (2, "Bedroom Lamp".to_string()),
+        ]);
+        let groups = LightGroupGraph {
+            groups: HashMap::from([
+                (
+                    10,
+                    LightGroup {
+                        name: "Kitchen".to_string(),
+                        light_ids: vec![1],
+                    },
+                ),
+                (
+                    20,
+                    LightGroup {
+                        name: "Bedroom".to_string(),
+                        light_ids: vec![2],
+                    },
+                ),
+            ]),
+        };
+        (lights, groups)
+    }
+
+    #[test]
+    fn resolve_targets_prefers_an_exact_light_name_over_its_group() {
+        let (lights, groups) = test_graph();
+        assert_eq!(
+            resolve_targets("kitchen lamp", &lights, &groups, 0.5),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn resolve_targets_expands_a_group_name_to_its_lights() {
+        let (lights, groups) = test_graph();
+        assert_eq!(resolve_targets("kitchen", &lights, &groups, 0.5), vec![1]);
+    }
+
+    #[test]
+    fn resolve_targets_rejects_unrelated_transcript_noise() {
+        let (lights, groups) = test_graph();
+        assert!(resolve_targets("asdf", &lights, &groups, 0.5).is_empty());
+    }
+
+    #[test]
+    fn resolve_targets_matches_across_word_order() {
+        let (lights, groups) = test_graph();
+        assert_eq!(
+            resolve_targets("lamp kitchen", &lights, &groups, 0.5),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn jaccard_similarity_scores() {
+        assert_eq!(jaccard_similarity("kitchen lamp", "kitchen lamp"), 1.0);
+        assert_eq!(jaccard_similarity("kitchen", "kitchen lamp"), 0.5);
+        assert_eq!(jaccard_similarity("asdf", "kitchen lamp"), 0.0);
+    }
+}
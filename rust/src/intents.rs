@@ -0,0 +1,173 @@
+// intents.rs
+//
+// Pluggable intent/action subsystem: each registered `IntentHandler`
+// advertises a name and a JSON tool schema for a single action, and
+// `dispatch_transcript_to_intents` fans a transcript out to every enabled
+// handler's model in one streaming chat completion, routing each tool call
+// back to the handler that owns it. `hue::LightIntentHandler` is the only
+// handler today; new voice-controlled devices register one alongside it in
+// `AppState::new` without touching this dispatch loop.
+
+use crate::ui::AppState;
+use anyhow::Context;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[async_trait]
+pub trait IntentHandler: Send + Sync {
+    /// Must match the `name` this handler's `action_schema` tool advertises.
+    fn name(&self) -> &str;
+    /// An OpenAI/Ollama-style tool schema describing this handler's single
+    /// action, folded into the combined `tools` array sent to the model.
+    fn action_schema(&self) -> Value;
+    /// Extra system-prompt text this handler needs the model to see (e.g.
+    /// known device names). Most handlers don't need any.
+    async fn system_context(&self, _app_state: &AppState) -> String {
+        String::new()
+    }
+    /// Executes this handler's action with the arguments the model supplied.
+    async fn execute(&self, app_state: &AppState, arguments: Value) -> anyhow::Result<()>;
+}
+
+/// A registered handler plus whether it's currently switched on, mirroring
+/// `callbacks::RegisteredCallback`.
+pub struct RegisteredIntentHandler {
+    pub handler: Box<dyn IntentHandler>,
+    pub enabled: bool,
+}
+
+impl RegisteredIntentHandler {
+    pub fn enabled(handler: Box<dyn IntentHandler>) -> Self {
+        RegisteredIntentHandler {
+            handler,
+            enabled: true,
+        }
+    }
+
+    pub fn disabled(handler: Box<dyn IntentHandler>) -> Self {
+        RegisteredIntentHandler {
+            handler,
+            enabled: false,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ChatToolCallFunction {
+    name: String,
+    arguments: Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChatToolCall {
+    function: ChatToolCallFunction,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ChatMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ChatToolCall>,
+}
+
+/// One line of the model's NDJSON streaming `/api/chat` response.
+#[derive(Deserialize, Debug)]
+struct ChatStreamChunk {
+    #[serde(default)]
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+}
+
+/// Sends `transcript` to `app_state.config.intent_model` along with every
+/// enabled handler's tool schema, and executes each tool call as soon as it
+/// streams in rather than waiting for the whole response. Non-tool-call
+/// content tokens are surfaced in the activity log as they arrive so a long
+/// response doesn't look like it's hung.
+pub async fn dispatch_transcript_to_intents(
+    app_state: &AppState,
+    handlers: &[RegisteredIntentHandler],
+    transcript: &str,
+) -> anyhow::Result<()> {
+    let enabled: Vec<&RegisteredIntentHandler> = handlers.iter().filter(|h| h.enabled).collect();
+    if enabled.is_empty() {
+        return Ok(());
+    }
+
+    let tools: Vec<Value> = enabled.iter().map(|h| h.handler.action_schema()).collect();
+
+    let mut system_prompt = "You are a voice assistant that controls smart devices. Call the \
+        matching tool for each action the user wants taken. If it seems like the user is not \
+        talking to you, don't call any tool."
+        .to_string();
+    for handler in &enabled {
+        let context = handler.handler.system_context(app_state).await;
+        if !context.is_empty() {
+            system_prompt.push_str("\n\n");
+            system_prompt.push_str(&context);
+        }
+    }
+
+    let client = reqwest::Client::new();
+    let mut response = client
+        .post(&app_state.config.intent_model_url)
+        .json(&serde_json::json!({
+            "model": app_state.config.intent_model,
+            "messages": [
+                { "role": "system", "content": system_prompt },
+                { "role": "user", "content": transcript },
+            ],
+            "tools": tools,
+            "stream": true,
+        }))
+        .send()
+        .await?;
+
+    // The model streams one JSON object per line. Act on tool calls and
+    // surface content tokens as soon as each line arrives instead of
+    // buffering the whole response first.
+    let mut buffer: Vec<u8> = Vec::new();
+    'stream: while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        while let Some(newline_pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line = String::from_utf8_lossy(&buffer[..newline_pos])
+                .trim()
+                .to_string();
+            buffer.drain(..=newline_pos);
+            if line.is_empty() {
+                continue;
+            }
+            let chat_chunk: ChatStreamChunk = serde_json::from_str(&line)
+                .context("Failed to parse intent model stream chunk")?;
+            let message = chat_chunk.message.unwrap_or_default();
+            if !message.content.is_empty() {
+                app_state.push_activity_log(format!("[intents] {}", message.content));
+            }
+            for tool_call in message.tool_calls {
+                let Some(registered) = enabled
+                    .iter()
+                    .find(|h| h.handler.name() == tool_call.function.name)
+                else {
+                    continue;
+                };
+                if let Err(e) = registered
+                    .handler
+                    .execute(app_state, tool_call.function.arguments)
+                    .await
+                {
+                    app_state.push_activity_log(format!(
+                        "Intent handler {} failed: {}",
+                        tool_call.function.name, e
+                    ));
+                }
+            }
+            if chat_chunk.done {
+                break 'stream;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -20,6 +20,13 @@ pub fn get_logs_path() -> Result<std::path::PathBuf> {
     Ok(dir.join("mic.log"))
 }
 
+/// Directory the rotating activity/audit JSONL files are written under,
+/// alongside the main log file.
+pub fn get_activity_log_dir() -> Result<std::path::PathBuf> {
+    let project_dirs = get_project_dirs()?;
+    Ok(project_dirs.data_dir().join("activity"))
+}
+
 // Define a custom writer that flushes after every write
 struct FlushingWriter {
     file: Mutex<File>, // Wrap the file in a mutex to safely access it across threads
@@ -1,10 +1,18 @@
 // main.rs
 
+mod activity;
+mod callbacks;
 mod config;
+mod discord;
+mod hue;
+mod intents;
 mod logging;
 mod microphone;
+mod server;
 mod transcription;
 mod ui;
+mod vad;
+mod wake_word;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
@@ -23,6 +31,66 @@ struct Cli {
     command: Option<Commands>,
     #[arg(long)]
     config_path: Option<String>,
+    /// Capture from only this microphone for this run, overriding
+    /// `microphones` in the config file. Also settable via `MIC_MICROPHONE`.
+    #[arg(long)]
+    microphone: Option<String>,
+    /// Override `intent_model` for this run. Also settable via `MIC_MODEL`.
+    #[arg(long)]
+    model: Option<String>,
+    /// Override `transcription_results_dir` for this run. Also settable via
+    /// `MIC_OUTPUT_DIR`.
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+}
+
+/// CLI-flag/env-var overrides layered onto the loaded file config, so a
+/// single setting can be tweaked for one run without editing `config.json`.
+/// Precedence is CLI flag > env var > config file > default; a `None` field
+/// here leaves whatever `AppConfig::load` produced untouched.
+#[derive(Debug, Default)]
+struct CliOverrides {
+    microphone: Option<String>,
+    model: Option<String>,
+    output_dir: Option<PathBuf>,
+}
+
+impl CliOverrides {
+    /// Reads the `MIC_MICROPHONE`/`MIC_MODEL`/`MIC_OUTPUT_DIR` environment
+    /// variables.
+    fn from_env() -> Self {
+        CliOverrides {
+            microphone: std::env::var("MIC_MICROPHONE").ok(),
+            model: std::env::var("MIC_MODEL").ok(),
+            output_dir: std::env::var("MIC_OUTPUT_DIR").ok().map(PathBuf::from),
+        }
+    }
+
+    /// Layers `cli`'s flags over `self` (expected to already hold the
+    /// env-var values); a set CLI flag always wins.
+    fn layer_cli(self, cli: &Cli) -> Self {
+        CliOverrides {
+            microphone: cli.microphone.clone().or(self.microphone),
+            model: cli.model.clone().or(self.model),
+            output_dir: cli.output_dir.clone().or(self.output_dir),
+        }
+    }
+
+    /// Applies the `model`/`output_dir` overrides onto `config`. `microphone`
+    /// is deliberately not handled here: it's passed straight to
+    /// `hook_microphones` instead of being written into `config.microphones`,
+    /// since `config` is the same `AppConfig` `AppState` persists from, and
+    /// baking a "for this run only" device restriction into it would make
+    /// the next unrelated mid-session config save (e.g. muting a different
+    /// mic) permanently disable every other device.
+    fn apply_to(&self, config: &mut AppConfig) {
+        if let Some(model) = &self.model {
+            config.intent_model = model.clone();
+        }
+        if let Some(dir) = &self.output_dir {
+            config.transcription_results_dir = dir.clone();
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -34,8 +102,16 @@ enum Commands {
         #[command(subcommand)]
         action: TranscriptAction,
     },
-    /// Get the config path
-    Config,
+    /// Inspect or scaffold the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// List discovered Hue lights
+    Lights,
+    /// Launch the interactive app with every mic armed for the configured
+    /// wake word instead of capturing on voice activity right away.
+    Listen,
 }
 
 #[derive(Subcommand, Debug)]
@@ -44,6 +120,27 @@ enum TranscriptAction {
     PathsList,
     /// Show the latest transcription
     ShowLatest,
+    /// Transcribe one or more existing WAV files (or `-` for stdin) through
+    /// the same backend live capture uses, saving results into the normal
+    /// transcript store
+    File {
+        /// WAV file paths, or `-` to read a WAV stream from stdin
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print the resolved config path
+    Path,
+    /// Write a default config to the resolved path
+    Init {
+        /// Overwrite an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the full default config (every field populated) to stdout
+    DumpDefault,
 }
 
 pub fn get_project_dirs() -> Result<ProjectDirs> {
@@ -55,6 +152,32 @@ pub fn get_config_path() -> Result<PathBuf> {
     Ok(project_dirs.config_dir().join("config.json"))
 }
 
+/// Candidate file names checked in each directory by `lookup_project_config`,
+/// in priority order.
+const PROJECT_CONFIG_NAMES: [&str; 2] = [".mic.json", "mic.json"];
+
+/// Walks upward from `start` looking for a project-local config file, the
+/// same way tools like `.git` or `.eslintrc` get discovered from anywhere
+/// inside a project. Returns the first match, or `None` if no ancestor
+/// directory (including `start` itself) has one.
+pub fn lookup_project_config(start: &std::path::Path) -> Result<Option<PathBuf>> {
+    let mut current = start.canonicalize().context("Failed to canonicalize start directory")?;
+    loop {
+        for name in PROJECT_CONFIG_NAMES {
+            let candidate = current.join(name);
+            // `is_file()` also returns false for a directory named `mic.json`,
+            // which would otherwise be mistaken for a config file below.
+            if candidate.is_file() {
+                return Ok(Some(candidate));
+            }
+        }
+        match current.parent() {
+            Some(parent) => current = parent.to_path_buf(),
+            None => return Ok(None),
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
@@ -64,17 +187,37 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Determine config path
+    // Determine config path: an explicit `--config-path` wins, then the
+    // nearest project-local config found by walking up from the current
+    // directory, then the default `ProjectDirs` location.
     let config_path = match cli.config_path.map(PathBuf::from) {
         Some(path) => path,
-        None => get_config_path()?,
+        None => {
+            let cwd = std::env::current_dir().context("Failed to determine current directory")?;
+            match lookup_project_config(&cwd)? {
+                Some(path) => path,
+                None => get_config_path()?,
+            }
+        }
     };
 
     debug!("Using config path: {:?}", config_path);
 
+    // `Config` actions are handled before `AppConfig::load` runs, since
+    // `load` itself writes a default config to `config_path` the moment it's
+    // missing; that would make `Init`'s overwrite check meaningless and
+    // would write a file out from under a plain `DumpDefault`/`Path` call.
+    if let Some(Commands::Config { action }) = &cli.command {
+        return handle_config_command(action, &config_path);
+    }
+
     // Load configuration
     let mut config = AppConfig::load(&config_path)?;
 
+    // Layer CLI flags and `MIC_*` env vars over the loaded file config.
+    let overrides = CliOverrides::from_env().layer_cli(&cli);
+    overrides.apply_to(&mut config);
+
     info!("Running app with command {:?}", cli.command);
 
     match cli.command {
@@ -88,13 +231,20 @@ async fn main() -> Result<()> {
             TranscriptAction::ShowLatest => {
                 show_latest_transcript(&config)?;
             }
+            TranscriptAction::File { paths } => {
+                transcription::transcribe_files(&config, &paths)?;
+            }
         },
-        Some(Commands::Config) => {
-            println!("{}", config_path.display());
+        Some(Commands::Config { .. }) => unreachable!("handled above"),
+        Some(Commands::Lights) => {
+            hue::print_lights(&config).await?;
+        }
+        Some(Commands::Listen) => {
+            ui::run_listen_mode(config, config_path, overrides.microphone).await?;
         }
         None => {
             // Launch interactive application
-            ui::run_app(&mut config).await?;
+            ui::run_app(config, config_path, overrides.microphone).await?;
         }
     }
 
@@ -102,6 +252,32 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn handle_config_command(action: &ConfigAction, config_path: &PathBuf) -> Result<()> {
+    match action {
+        ConfigAction::Path => {
+            println!("{}", config_path.display());
+        }
+        ConfigAction::Init { force } => {
+            if config_path.exists() && !force {
+                anyhow::bail!(
+                    "Config already exists at {}; pass --force to overwrite",
+                    config_path.display()
+                );
+            }
+            if let Some(parent) = config_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let config_data = serde_json::to_string_pretty(&AppConfig::default())?;
+            std::fs::write(config_path, config_data)?;
+            println!("Wrote default config to {}", config_path.display());
+        }
+        ConfigAction::DumpDefault => {
+            println!("{}", serde_json::to_string_pretty(&AppConfig::default())?);
+        }
+    }
+    Ok(())
+}
+
 fn list_microphones_command() -> Result<()> {
     let microphones = list_microphones();
     for mic in microphones {
@@ -109,3 +285,56 @@ fn list_microphones_command() -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli_with(microphone: Option<&str>, model: Option<&str>, output_dir: Option<&str>) -> Cli {
+        Cli {
+            command: None,
+            config_path: None,
+            microphone: microphone.map(String::from),
+            model: model.map(String::from),
+            output_dir: output_dir.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn layer_cli_prefers_a_set_cli_flag_over_the_env_var() {
+        let env = CliOverrides {
+            microphone: Some("env-mic".to_string()),
+            model: Some("env-model".to_string()),
+            output_dir: None,
+        };
+        let cli = cli_with(Some("cli-mic"), None, None);
+        let result = env.layer_cli(&cli);
+        assert_eq!(result.microphone.as_deref(), Some("cli-mic"));
+        assert_eq!(result.model.as_deref(), Some("env-model"));
+    }
+
+    #[test]
+    fn layer_cli_falls_back_to_the_env_var_when_no_cli_flag_is_set() {
+        let env = CliOverrides {
+            microphone: Some("env-mic".to_string()),
+            model: None,
+            output_dir: None,
+        };
+        let cli = cli_with(None, None, None);
+        let result = env.layer_cli(&cli);
+        assert_eq!(result.microphone.as_deref(), Some("env-mic"));
+    }
+
+    #[test]
+    fn apply_to_leaves_config_microphones_untouched() {
+        let overrides = CliOverrides {
+            microphone: Some("some-device".to_string()),
+            model: Some("some-model".to_string()),
+            output_dir: None,
+        };
+        let mut config = AppConfig::default();
+        overrides.apply_to(&mut config);
+        assert!(config.microphones.is_empty());
+        assert_eq!(config.intent_model, "some-model");
+    }
+}
@@ -1,11 +1,14 @@
 // microphone.rs
+use crate::config::{CustomAudioDeviceConfig, CustomSampleFormat, PttModifier};
 use crate::ui::AppState;
+use crate::vad::SpectralVad;
 use anyhow::Context;
+use chrono::{DateTime, Local};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{ChannelCount, SampleFormat, SampleRate, Stream};
+use cpal::{BufferSize, ChannelCount, SampleFormat, SampleRate, Stream, SupportedStreamConfig};
 use rubato::Resampler;
 use tokio::sync::mpsc::UnboundedSender;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 pub const SAMPLE_RATE: SampleRate = SampleRate(16_000);
 
@@ -13,6 +16,34 @@ pub struct Microphone {
     pub name: String,
     pub state: MicrophoneState,
     pub stream: Option<Stream>,
+    /// Silences this mic's audio before it ever reaches voice-activity
+    /// detection, independent of `Disabled`/enabled state.
+    pub muted: bool,
+    /// Gain, 0-100, applied in software by scaling samples in
+    /// `process_raw_audio`; the device's own volume control is not touched.
+    pub gain: u8,
+    /// This mic's adaptive-noise-floor voice detector. Lives here rather
+    /// than inside `MicrophoneState` because its noise floor and hangover
+    /// counter need to persist across the `WaitingForVoiceActivity` <->
+    /// `VoiceActivated` transitions, not just for the lifetime of one
+    /// `ActiveMicrophoneState`.
+    pub vad: SpectralVad,
+    /// Push-to-talk hotkey, mirrored from `MicrophoneConfig`. `None` means
+    /// this mic uses voice-activity detection instead of a hotkey.
+    pub push_to_talk_key: Option<char>,
+    pub push_to_talk_modifier: Option<PttModifier>,
+    /// Frame index `process_raw_audio` expects the next `AudioChunk` to
+    /// start at, derived from the previous chunk's `sample_index` plus its
+    /// length. `None` until the first chunk arrives.
+    pub expected_sample_index: Option<u64>,
+    /// This mic's resampler to `SAMPLE_RATE`, if its stream runs at a
+    /// different rate. Kept here (rather than built fresh per chunk) so
+    /// leftover samples from one `AudioChunk::downmix` call carry over into
+    /// the next instead of being zero-padded away, which used to leave an
+    /// audible click at every chunk boundary. `None` for mics that don't
+    /// need resampling, or before `hook_microphone` has learned the stream's
+    /// actual sample rate.
+    pub resampler: Option<MicResampler>,
 }
 
 #[derive(Clone, Debug)]
@@ -22,6 +53,11 @@ pub enum MicrophoneState {
     PushToTalkActivated(ActiveMicrophoneState),
     WaitingForVoiceActivity,
     VoiceActivated(ActiveMicrophoneState),
+    /// `Commands::Listen` wake-word mode: same VAD-gated capture as
+    /// `WaitingForVoiceActivity`, but flushed utterances go to a wake-word
+    /// check instead of straight to transcription.
+    WaitingForWakeWord,
+    WakeWordActivated(ActiveMicrophoneState),
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +66,11 @@ pub struct ActiveMicrophoneState {
     pub last_activity: std::time::Instant,
     pub data_so_far: Vec<f32>, // Assuming f32 samples
     pub sample_rate: SampleRate,
+    /// Wall-clock time the first sample of this utterance was captured,
+    /// taken from the triggering chunk's `AudioChunk::captured_at`. More
+    /// accurate than stamping `Local::now()` when the utterance later ends,
+    /// since that moment has drifted by however long the utterance lasted.
+    pub captured_at: DateTime<Local>,
 }
 
 pub fn list_microphones() -> Vec<String> {
@@ -40,34 +81,53 @@ pub fn list_microphones() -> Vec<String> {
         .collect()
 }
 
-pub fn hook_microphones(state: &mut AppState) -> anyhow::Result<()> {
+/// `only_microphone`, when set (from `--microphone`/`MIC_MICROPHONE`),
+/// restricts this run to that one device regardless of what `enabled` says
+/// in `state.config.microphones` — applied here rather than baked into
+/// `state.config` so it can't get written back to disk by a later
+/// `persist_selected_microphone_config` call.
+pub fn hook_microphones(state: &mut AppState, only_microphone: Option<&str>) -> anyhow::Result<()> {
     let host = cpal::default_host();
     let devices = host.input_devices().unwrap();
     for (i, device) in devices.enumerate() {
         let name = device.name().unwrap_or_else(|_| format!("Unknown-{i}"));
 
-        let enabled = state
-            .config
-            .microphones
-            .get(&name)
-            .map(|config| config.enabled)
-            .unwrap_or(true);
+        let mic_config = state.config.microphones.get(&name);
+        let enabled = match only_microphone {
+            Some(only) => name == only,
+            None => mic_config.map(|config| config.enabled).unwrap_or(true),
+        };
+        let muted = mic_config.map(|config| config.muted).unwrap_or(false);
+        let gain = mic_config.map(|config| config.gain).unwrap_or(100);
+        let push_to_talk_key = mic_config.and_then(|config| config.push_to_talk_key);
+        let push_to_talk_modifier = mic_config.and_then(|config| config.push_to_talk_modifier);
 
         let microphone = Microphone {
             name: name.clone(),
-            state: if enabled {
-                MicrophoneState::WaitingForVoiceActivity
-            } else {
+            state: if !enabled {
                 MicrophoneState::Disabled
+            } else if push_to_talk_key.is_some() {
+                MicrophoneState::WaitingForPushToTalk
+            } else {
+                MicrophoneState::WaitingForVoiceActivity
             },
             stream: None,
+            muted,
+            gain,
+            vad: SpectralVad::new(),
+            push_to_talk_key,
+            push_to_talk_modifier,
+            expected_sample_index: None,
+            resampler: None,
         };
-        if enabled {
-            info!("Hooking microphone {}", name);
-            hook_microphone(state, microphone)?;
-        } else {
-            info!("Skipping microphone {}", name);
-        }
+        // Every device is hooked (its stream opened) regardless of
+        // `enabled`, not just the enabled ones: `process_raw_audio` already
+        // no-ops on `MicrophoneState::Disabled`, and only hooking enabled
+        // mics meant a disabled one never landed in `microphones`/
+        // `microphone_order` at all, so the UI had nothing to cycle back to
+        // in order to re-enable it short of hand-editing config.json.
+        info!("Hooking microphone {} (enabled: {})", name, enabled);
+        hook_microphone(state, microphone, i)?;
     }
     Ok(())
 }
@@ -77,18 +137,88 @@ pub struct AudioChunk {
     pub channels: ChannelCount,
     pub sample_rate: SampleRate,
     pub data: Vec<f32>,
+    /// Time since the input stream was opened, from cpal's
+    /// `InputCallbackInfo::timestamp().capture`. Used to detect dropped
+    /// buffers; `Duration::ZERO` on chunks assembled after the fact (e.g. a
+    /// flushed utterance), where it isn't meaningful.
+    pub capture_time: std::time::Duration,
+    /// Running count of frames captured on this mic's stream so far, as of
+    /// the start of this chunk. `process_raw_audio` compares this against
+    /// the previous chunk's end to notice gaps from a buffer under/overrun.
+    pub sample_index: u64,
+    /// Wall-clock time this chunk (or, for a flushed utterance, its first
+    /// sample) was captured.
+    pub captured_at: DateTime<Local>,
+}
+
+/// Averages interleaved samples across `channels` into mono.
+fn downmix_interleaved_to_mono(data: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return data.to_vec();
+    }
+    data.chunks(channels)
+        .map(|chunk| chunk.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Frames per buffer handed to the underlying FFT resampler.
+pub const RESAMPLE_CHUNK_FRAMES: usize = 441;
+
+/// Resamples a single mono stream to `SAMPLE_RATE`, persisting both the
+/// `rubato` FFT plan and any leftover samples across calls. Rebuilding the
+/// resampler and zero-padding the tail of every chunk (the old
+/// `AudioChunk::downmix` behaviour) wasted CPU on replanning the FFT and
+/// injected silence at every chunk boundary, which came out as an audible
+/// click once resampled audio was actually played or transcribed.
+pub struct MicResampler {
+    resampler: rubato::FftFixedInOut<f32>,
+    /// Samples carried over from the previous `process` call that weren't
+    /// enough to fill a full `RESAMPLE_CHUNK_FRAMES` block yet.
+    pending: Vec<f32>,
+}
+
+impl MicResampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> anyhow::Result<Self> {
+        let resampler = rubato::FftFixedInOut::<f32>::new(
+            input_rate as usize,
+            output_rate as usize,
+            RESAMPLE_CHUNK_FRAMES,
+            1,
+        )
+        .context("Failed to create resampler")?;
+        Ok(MicResampler {
+            resampler,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Resamples `mono_samples`, buffering any leftover tail for the next
+    /// call instead of padding it with zeros.
+    pub fn process(&mut self, mono_samples: &[f32]) -> Vec<f32> {
+        self.pending.extend_from_slice(mono_samples);
+
+        let mut resampled = Vec::new();
+        while self.pending.len() >= RESAMPLE_CHUNK_FRAMES {
+            let remainder = self.pending.split_off(RESAMPLE_CHUNK_FRAMES);
+            let block = std::mem::replace(&mut self.pending, remainder);
+            let output = self
+                .resampler
+                .process(&[block], None)
+                .expect("Resampling failed");
+            for channel_data in output {
+                resampled.extend(channel_data);
+            }
+        }
+        resampled
+    }
 }
 
 impl AudioChunk {
-    pub fn downmix(mut self) -> Self {
+    pub fn downmix(mut self, resampler: &mut MicResampler) -> Self {
         // convert to mono
         if self.channels > 1 {
             debug!("Downmixing audio from {} channels to 1", self.channels);
-            self.data = self
-                .data
-                .chunks(self.channels as usize)
-                .map(|chunk| chunk.iter().sum::<f32>() / self.channels as f32)
-                .collect();
+            self.data = downmix_interleaved_to_mono(&self.data, self.channels as usize);
             self.channels = 1;
         }
         // resample to 16khz
@@ -99,49 +229,13 @@ impl AudioChunk {
                 self.sample_rate.0,
                 SAMPLE_RATE.0
             );
-            let chunk_size = 441; // frames per buffer
-            let mut resampler = rubato::FftFixedInOut::<f32>::new(
-                self.sample_rate.0 as usize, // 48,000
-                SAMPLE_RATE.0 as usize,      // 16,000
-                chunk_size,
-                self.channels as usize,
-            )
-            .expect("Failed to create resampler");
-
-            let mut resampled_data = Vec::new();
-            for chunk in self.data.chunks(chunk_size) {
-                // If the last chunk is smaller than chunk_size, pad it with zeros
-                let mut input_chunk = chunk.to_vec();
-                if input_chunk.len() < chunk_size {
-                    input_chunk.resize(chunk_size, 0.0);
-                }
-
-                let output = resampler
-                    .process(&[input_chunk], None)
-                    .expect("Resampling failed");
-
-                for channel_data in output {
-                    resampled_data.extend(channel_data);
-                }
-            }
-
             let before = self.data.len();
-            self.data = resampled_data;
-            let after = self.data.len();
-            let ratio = self.sample_rate.0 as f32 / SAMPLE_RATE.0 as f32;
+            self.data = resampler.process(&self.data);
             debug!(
-                "Resampling complete: {} samples -> {} samples (ratio: {})",
-                before, after, ratio
+                "Resampling complete: {} samples -> {} samples",
+                before,
+                self.data.len()
             );
-
-            let observed_ratio = before as f32 / after as f32;
-            if (observed_ratio - ratio).abs() > 1.0 {
-                error!(
-                    "Resampling failed: {} samples -> {} samples, expected ratio {} observed ratio {}",
-                    before, after, ratio, observed_ratio
-                );
-            }
-
             self.sample_rate = SAMPLE_RATE;
         }
         self
@@ -156,38 +250,146 @@ impl AudioChunk {
     }
 }
 
-pub fn hook_microphone(app_state: &mut AppState, mut mic: Microphone) -> anyhow::Result<()> {
+fn custom_sample_format_matches(format: CustomSampleFormat, actual: SampleFormat) -> bool {
+    matches!(
+        (format, actual),
+        (CustomSampleFormat::F32, SampleFormat::F32)
+            | (CustomSampleFormat::I16, SampleFormat::I16)
+            | (CustomSampleFormat::U16, SampleFormat::U16)
+    )
+}
+
+/// Returns the first config in `configs` whose matcher matches this device's
+/// name/enumeration index.
+fn find_custom_device_config(
+    configs: &[CustomAudioDeviceConfig],
+    name: &str,
+    index: usize,
+) -> Option<&CustomAudioDeviceConfig> {
+    configs.iter().find(|c| c.matcher.matches(name, index))
+}
+
+/// Picks the supported input config closest to `requested`'s sample
+/// format/channel count, falling back to `device.default_input_config()`
+/// when nothing is requested or nothing supported could be scored.
+fn select_input_config(
+    device: &cpal::Device,
+    requested: Option<&CustomAudioDeviceConfig>,
+) -> anyhow::Result<SupportedStreamConfig> {
+    let Some(requested) = requested else {
+        return device
+            .default_input_config()
+            .context("No default input config available");
+    };
+
+    let best_range = device
+        .supported_input_configs()
+        .context("Failed to query supported input configs")?
+        .max_by_key(|range| {
+            let mut score = 0i64;
+            if let Some(channels) = requested.channels {
+                score -= (range.channels() as i64 - channels as i64).abs() * 1000;
+            }
+            if let Some(format) = requested.sample_format {
+                if custom_sample_format_matches(format, range.sample_format()) {
+                    score += 10_000;
+                }
+            }
+            score
+        });
+
+    let Some(range) = best_range else {
+        return device
+            .default_input_config()
+            .context("No default input config available");
+    };
+
+    let sample_rate = match requested.sample_rate.map(SampleRate) {
+        Some(rate) if rate < range.min_sample_rate() => range.min_sample_rate(),
+        Some(rate) if rate > range.max_sample_rate() => range.max_sample_rate(),
+        Some(rate) => rate,
+        None => range.max_sample_rate(),
+    };
+    Ok(range.with_sample_rate(sample_rate))
+}
+
+/// Time elapsed since this stream's first callback, using cpal's own
+/// `StreamInstant` clock rather than wall time so it isn't affected by
+/// scheduling jitter between callbacks and the code reading them.
+fn capture_time_since_stream_start(
+    info: &cpal::InputCallbackInfo,
+    stream_clock_start: &mut Option<cpal::StreamInstant>,
+) -> std::time::Duration {
+    let capture_instant = info.timestamp().capture;
+    let epoch = *stream_clock_start.get_or_insert(capture_instant);
+    capture_instant.duration_since(&epoch).unwrap_or_default()
+}
+
+pub fn hook_microphone(
+    app_state: &mut AppState,
+    mut mic: Microphone,
+    device_index: usize,
+) -> anyhow::Result<()> {
     let device = cpal::default_host()
         .input_devices()?
         .find(|d| d.name().unwrap_or_default() == mic.name)
         .context("Microphone not found")?;
-    let config = device.default_input_config().unwrap();
-    let sample_rate = config.sample_rate();
-    let channels = config.channels();
-    let sample_format = config.sample_format();
-    let config = config.into();
+    let custom_config =
+        find_custom_device_config(&app_state.config.audio_devices, &mic.name, device_index)
+            .cloned();
+    let supported_config = select_input_config(&device, custom_config.as_ref())?;
+    let sample_rate = supported_config.sample_rate();
+    let channels = supported_config.channels();
+    let sample_format = supported_config.sample_format();
+    let mut config: cpal::StreamConfig = supported_config.into();
+    config.buffer_size = custom_config
+        .as_ref()
+        .and_then(|c| c.buffering)
+        .map(|b| BufferSize::Fixed(b.frames))
+        .unwrap_or(BufferSize::Default);
     let err_fn = |err| error!("An error occurred on the input stream: {}", err);
     let mic_name = mic.name.clone();
-    info!("Starting stream for mic {mic_name} with format {sample_format:?}");
+    info!(
+        "Starting stream for mic {mic_name} with format {sample_format:?}, {}Hz, {} channel(s), buffer {:?}",
+        sample_rate.0, channels, config.buffer_size
+    );
+    app_state.push_activity_log(format!(
+        "Mic {mic_name}: {sample_format:?} {}Hz x{} channel(s), buffer {:?}",
+        sample_rate.0, channels, config.buffer_size
+    ));
     let chunk_sender = app_state.raw_audio_sender.clone();
+    // Anchors `InputCallbackInfo`'s stream-relative `capture` timestamps to a
+    // wall clock: the first callback's `StreamInstant` becomes t=0, and
+    // `stream_opened_at` is the wall time closest to that t=0.
+    let stream_opened_at = Local::now();
+    let mut stream_clock_start: Option<cpal::StreamInstant> = None;
+    let mut sample_index: u64 = 0;
     let stream = match sample_format {
         SampleFormat::F32 => device.build_input_stream(
             &config,
-            move |data: &[f32], _| {
+            move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                let capture_time = capture_time_since_stream_start(info, &mut stream_clock_start);
+                let frames = data.len() / channels.max(1) as usize;
                 chunk_sender
                     .send(AudioChunk {
                         mic_name: mic_name.to_string(),
                         channels,
                         sample_rate,
                         data: data.to_vec(),
+                        capture_time,
+                        sample_index,
+                        captured_at: stream_opened_at + chrono::Duration::from_std(capture_time).unwrap_or_default(),
                     })
                     .expect("Failed to send audio data");
+                sample_index += frames as u64;
             },
             err_fn,
         ),
         SampleFormat::I16 => device.build_input_stream(
             &config,
-            move |data: &[i16], _| {
+            move |data: &[i16], info: &cpal::InputCallbackInfo| {
+                let capture_time = capture_time_since_stream_start(info, &mut stream_clock_start);
+                let frames = data.len() / channels.max(1) as usize;
                 let data_f32: Vec<f32> = data.iter().map(|&s| s as f32 / 32768.0).collect();
                 chunk_sender
                     .send(AudioChunk {
@@ -195,14 +397,20 @@ pub fn hook_microphone(app_state: &mut AppState, mut mic: Microphone) -> anyhow:
                         channels,
                         sample_rate,
                         data: data_f32,
+                        capture_time,
+                        sample_index,
+                        captured_at: stream_opened_at + chrono::Duration::from_std(capture_time).unwrap_or_default(),
                     })
                     .expect("Failed to send audio data");
+                sample_index += frames as u64;
             },
             err_fn,
         ),
         SampleFormat::U16 => device.build_input_stream(
             &config,
-            move |data: &[u16], _| {
+            move |data: &[u16], info: &cpal::InputCallbackInfo| {
+                let capture_time = capture_time_since_stream_start(info, &mut stream_clock_start);
+                let frames = data.len() / channels.max(1) as usize;
                 let data_f32: Vec<f32> = data.iter().map(|&s| s as f32 / 65536.0 - 0.5).collect();
                 chunk_sender
                     .send(AudioChunk {
@@ -210,8 +418,12 @@ pub fn hook_microphone(app_state: &mut AppState, mut mic: Microphone) -> anyhow:
                         channels,
                         sample_rate,
                         data: data_f32,
+                        capture_time,
+                        sample_index,
+                        captured_at: stream_opened_at + chrono::Duration::from_std(capture_time).unwrap_or_default(),
                     })
                     .expect("Failed to send audio data");
+                sample_index += frames as u64;
             },
             err_fn,
         ),
@@ -220,35 +432,93 @@ pub fn hook_microphone(app_state: &mut AppState, mut mic: Microphone) -> anyhow:
 
     stream.play().context("Failed to start input stream")?;
     mic.stream = Some(stream);
+    mic.resampler = if sample_rate != SAMPLE_RATE {
+        Some(MicResampler::new(sample_rate.0, SAMPLE_RATE.0)?)
+    } else {
+        None
+    };
     app_state.add_microphone(mic);
     Ok(())
 }
 
 pub fn process_raw_audio(
-    chunk: AudioChunk,
-    state: &mut MicrophoneState,
+    mut chunk: AudioChunk,
+    mic: &mut Microphone,
     batch_audio_sender: &UnboundedSender<AudioChunk>,
+    // Where a `WaitingForWakeWord`/`WakeWordActivated` mic's flushed
+    // candidate utterances go instead of `batch_audio_sender`, since they
+    // need a wake-word check before they're a real dictation session.
+    // Ignored by mics not in wake-word mode.
+    wake_word_sender: &UnboundedSender<AudioChunk>,
+    // How long a `WakeWordActivated` mic waits for a pause before flushing
+    // its candidate utterance, from `AppConfig::wake_silence_timeout_secs`.
+    wake_silence_timeout: std::time::Duration,
+    // Whether this mic's configured push-to-talk hotkey is currently held.
+    // Ignored by mics in a voice-activity-detection state.
+    ptt_active: bool,
 ) {
-    let amplitude = chunk.data.iter().map(|&s| s.abs()).sum::<f32>() / chunk.data.len() as f32;
+    if mic.muted {
+        return;
+    }
+    if mic.gain != 100 {
+        let factor = mic.gain as f32 / 100.0;
+        for sample in chunk.data.iter_mut() {
+            *sample *= factor;
+        }
+    }
+
+    let incoming_frames = (chunk.data.len() / chunk.channels.max(1) as usize) as u64;
+    if let Some(expected) = mic.expected_sample_index {
+        if chunk.sample_index > expected {
+            let missing_frames = chunk.sample_index - expected;
+            warn!(
+                "Mic {} dropped ~{} frame(s) (buffer underrun/overrun); inserting silence",
+                chunk.mic_name, missing_frames
+            );
+            let silence = vec![0.0f32; missing_frames as usize * chunk.channels as usize];
+            chunk.data = [silence, std::mem::take(&mut chunk.data)].concat();
+        }
+    }
+    mic.expected_sample_index = Some(chunk.sample_index + incoming_frames);
+
+    // Downmix to mono and, if this mic's stream doesn't already run at
+    // `SAMPLE_RATE`, resample it through `mic.resampler` before the chunk
+    // goes anywhere else, so VAD, accumulation, and transcription all see
+    // the same mono 16kHz audio instead of each having to cope with raw
+    // device channels/rate.
+    if let Some(resampler) = mic.resampler.as_mut() {
+        chunk = chunk.downmix(resampler);
+    } else if chunk.channels > 1 {
+        chunk.data = downmix_interleaved_to_mono(&chunk.data, chunk.channels as usize);
+        chunk.channels = 1;
+    }
+
+    // Run the spectral VAD before taking a mutable borrow of `mic.state`, so
+    // the two fields can be borrowed independently below.
+    let mono = downmix_interleaved_to_mono(&chunk.data, chunk.channels as usize);
+    let is_speech = mic.vad.is_speech(&mono, chunk.sample_rate.0);
+
+    let state = &mut mic.state;
     // Process state transitions
     match state {
         MicrophoneState::Disabled => {
             // Do nothing
         }
         MicrophoneState::WaitingForVoiceActivity => {
-            if amplitude > 0.01 {
+            if is_speech {
                 // Voice activity detected
                 info!("Voice activity detected from mic {}", chunk.mic_name);
                 *state = MicrophoneState::VoiceActivated(ActiveMicrophoneState {
                     activity_started: std::time::Instant::now(),
                     last_activity: std::time::Instant::now(),
+                    captured_at: chunk.captured_at,
                     data_so_far: chunk.data,
                     sample_rate: chunk.sample_rate,
                 });
             }
         }
         MicrophoneState::VoiceActivated(active_state) => {
-            if amplitude > 0.01 {
+            if is_speech {
                 // Continue recording
                 active_state.last_activity = std::time::Instant::now();
                 active_state.data_so_far.extend_from_slice(&chunk.data);
@@ -267,6 +537,9 @@ pub fn process_raw_audio(
                         data: audio_data,
                         sample_rate: active_state.sample_rate,
                         channels: chunk.channels,
+                        capture_time: std::time::Duration::ZERO,
+                        sample_index: 0,
+                        captured_at: active_state.captured_at,
                     }) {
                         error!("Failed to send audio data for transcription: {:?}", e);
                         panic!("Failed to send audio data for transcription");
@@ -278,7 +551,87 @@ pub fn process_raw_audio(
                 }
             }
         }
-        MicrophoneState::WaitingForPushToTalk => todo!(),
-        MicrophoneState::PushToTalkActivated(_active_microphone_state) => todo!(),
+        MicrophoneState::WaitingForWakeWord => {
+            if is_speech {
+                info!("Possible wake word activity from mic {}", chunk.mic_name);
+                *state = MicrophoneState::WakeWordActivated(ActiveMicrophoneState {
+                    activity_started: std::time::Instant::now(),
+                    last_activity: std::time::Instant::now(),
+                    captured_at: chunk.captured_at,
+                    data_so_far: chunk.data,
+                    sample_rate: chunk.sample_rate,
+                });
+            }
+        }
+        MicrophoneState::WakeWordActivated(active_state) => {
+            if is_speech {
+                active_state.last_activity = std::time::Instant::now();
+                active_state.data_so_far.extend_from_slice(&chunk.data);
+            } else {
+                let elapsed = active_state.last_activity.elapsed();
+                if elapsed > wake_silence_timeout {
+                    info!(
+                        "Pause detected for mic {}, checking candidate for wake word",
+                        chunk.mic_name
+                    );
+                    let audio_data = std::mem::take(&mut active_state.data_so_far);
+                    if let Err(e) = wake_word_sender.send(AudioChunk {
+                        mic_name: chunk.mic_name,
+                        data: audio_data,
+                        sample_rate: active_state.sample_rate,
+                        channels: chunk.channels,
+                        capture_time: std::time::Duration::ZERO,
+                        sample_index: 0,
+                        captured_at: active_state.captured_at,
+                    }) {
+                        error!("Failed to send audio data for wake word check: {:?}", e);
+                        panic!("Failed to send audio data for wake word check");
+                    }
+                    // Always revert to listening; a confirmed match is armed
+                    // for real dictation out-of-band by whoever consumes
+                    // `wake_word_sender`.
+                    *state = MicrophoneState::WaitingForWakeWord;
+                } else {
+                    active_state.data_so_far.extend_from_slice(&chunk.data);
+                }
+            }
+        }
+        MicrophoneState::WaitingForPushToTalk => {
+            if ptt_active {
+                info!("Push-to-talk activated for mic {}", chunk.mic_name);
+                *state = MicrophoneState::PushToTalkActivated(ActiveMicrophoneState {
+                    activity_started: std::time::Instant::now(),
+                    last_activity: std::time::Instant::now(),
+                    captured_at: chunk.captured_at,
+                    data_so_far: chunk.data,
+                    sample_rate: chunk.sample_rate,
+                });
+            }
+        }
+        MicrophoneState::PushToTalkActivated(active_state) => {
+            if ptt_active {
+                active_state.last_activity = std::time::Instant::now();
+                active_state.data_so_far.extend_from_slice(&chunk.data);
+            } else {
+                info!(
+                    "Push-to-talk released for mic {}, sending data for transcription",
+                    chunk.mic_name
+                );
+                let audio_data = std::mem::take(&mut active_state.data_so_far);
+                if let Err(e) = batch_audio_sender.send(AudioChunk {
+                    mic_name: chunk.mic_name,
+                    data: audio_data,
+                    sample_rate: active_state.sample_rate,
+                    channels: chunk.channels,
+                    capture_time: std::time::Duration::ZERO,
+                    sample_index: 0,
+                    captured_at: active_state.captured_at,
+                }) {
+                    error!("Failed to send audio data for transcription: {:?}", e);
+                    panic!("Failed to send audio data for transcription");
+                }
+                *state = MicrophoneState::WaitingForPushToTalk;
+            }
+        }
     }
 }
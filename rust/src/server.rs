@@ -0,0 +1,107 @@
+// server.rs
+//
+// Local HTTP + SSE subsystem that mirrors transcription results so other
+// programs on the machine (editors, stream overlays, home-automation) can
+// subscribe without parsing the JSONL transcript files.
+
+use axum::extract::State;
+use axum::response::sse::{Event, Sse};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Local};
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{error, info};
+
+/// How many recent segments `GET /segments` keeps around for late subscribers.
+const RECENT_CAPACITY: usize = 200;
+
+/// One transcribed segment, tagged with the microphone it came from so
+/// consumers can filter per mic.
+#[derive(Clone, Serialize)]
+pub struct TranscriptSegmentEvent {
+    pub mic_name: String,
+    pub timestamp: DateTime<Local>,
+    pub text: String,
+}
+
+/// Shared state for the networked subsystem: a ring buffer of recent
+/// segments for the one-shot JSON route, and a broadcast channel fanning
+/// each new segment out to every connected `/events` subscriber.
+pub struct ServerState {
+    recent: Mutex<VecDeque<TranscriptSegmentEvent>>,
+    events_tx: broadcast::Sender<TranscriptSegmentEvent>,
+}
+
+impl ServerState {
+    pub fn new() -> Arc<Self> {
+        let (events_tx, _) = broadcast::channel(64);
+        Arc::new(ServerState {
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_CAPACITY)),
+            events_tx,
+        })
+    }
+
+    /// Records a segment and fans it out to any live SSE subscribers.
+    pub fn publish(&self, event: TranscriptSegmentEvent) {
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() >= RECENT_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(event.clone());
+        drop(recent);
+        // Err just means nobody's subscribed to /events right now.
+        let _ = self.events_tx.send(event);
+    }
+}
+
+/// Binds and runs the HTTP server on `addr` until the process exits.
+pub async fn serve(addr: SocketAddr, state: Arc<ServerState>) {
+    let app = Router::new()
+        .route("/segments", get(recent_segments))
+        .route("/events", get(sse_events))
+        .with_state(state);
+
+    info!("Serving transcription HTTP+SSE API on {}", addr);
+    match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => {
+            if let Err(e) = axum::serve(listener, app).await {
+                error!("Transcription server stopped: {}", e);
+            }
+        }
+        Err(e) => error!("Failed to bind transcription server on {}: {}", addr, e),
+    }
+}
+
+async fn recent_segments(State(state): State<Arc<ServerState>>) -> impl IntoResponse {
+    let recent = state.recent.lock().unwrap();
+    Json(recent.iter().cloned().collect::<Vec<_>>())
+}
+
+async fn sse_events(
+    State(state): State<Arc<ServerState>>,
+) -> Sse<impl futures::Stream<Item = Result<Event, Infallible>>> {
+    let rx = state.events_tx.subscribe();
+    let stream = BroadcastStream::new(rx).filter_map(|item| async move {
+        match item {
+            Ok(event) => match serde_json::to_string(&event) {
+                Ok(json) => Some(Ok(Event::default().data(json))),
+                Err(e) => {
+                    error!("Failed to serialize transcript event: {}", e);
+                    None
+                }
+            },
+            // Subscriber fell behind the broadcast channel; drop the gap
+            // rather than erroring the whole stream out.
+            Err(_) => None,
+        }
+    });
+    Sse::new(stream)
+}
@@ -1,22 +1,31 @@
 // transcription.rs
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, TranscriptFormat};
 use crate::microphone::AudioChunk;
 use anyhow::{Context, Result};
 use chrono::{DateTime, Local};
+use cpal::{ChannelCount, SampleRate};
 use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::debug;
 use std::fs::{self, OpenOptions};
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TranscriptionResult {
     pub segments: Vec<TranscriptionResultSegment>,
     pub language: String,
+    /// Wall-clock time the transcribed audio started being captured,
+    /// filled in locally from `AudioChunk::captured_at` after the
+    /// transcription API responds (the API itself doesn't know this).
+    /// More accurate than stamping `Local::now()` once transcription
+    /// finishes, since that has drifted by however long capture +
+    /// transcription took.
+    #[serde(default)]
+    pub captured_at: Option<DateTime<Local>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -97,6 +106,72 @@ fn generate_wav_data(audio_data: &[f32]) -> Result<Vec<u8>> {
     Ok(cursor.into_inner())
 }
 
+/// Feeds one or more pre-recorded WAV files (or, for `-`, a WAV stream piped
+/// in on stdin) through the same transcription backend the live capture path
+/// uses, saving each result into the normal transcript store so
+/// `list_transcript_paths`/`show_latest_transcript` pick it up.
+pub fn transcribe_files(config: &AppConfig, paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        let label = if path.as_os_str() == "-" {
+            "stdin".to_string()
+        } else {
+            path.display().to_string()
+        };
+        let (data, sample_rate, channels) = if path.as_os_str() == "-" {
+            read_wav_samples(std::io::stdin().lock())
+                .context("Failed to read WAV audio from stdin")?
+        } else {
+            let file = fs::File::open(path)
+                .with_context(|| format!("Failed to open audio file {}", path.display()))?;
+            read_wav_samples(std::io::BufReader::new(file))
+                .with_context(|| format!("Failed to read WAV audio from {}", path.display()))?
+        };
+
+        let captured_at = Local::now();
+        let chunk = AudioChunk {
+            mic_name: label.clone(),
+            channels,
+            sample_rate,
+            data,
+            capture_time: std::time::Duration::ZERO,
+            sample_index: 0,
+            captured_at,
+        };
+
+        let mut result = send_audio_for_transcription(&config.transcription_api_url, &chunk)
+            .with_context(|| format!("Failed to transcribe {}", label))?;
+        result.captured_at = Some(captured_at);
+        for segment in &result.segments {
+            println!("[{}] {}", label, segment.text);
+        }
+        save_transcription_result(config, &result, captured_at)?;
+    }
+    Ok(())
+}
+
+/// Decodes a WAV stream into `f32` samples (interleaved across the file's
+/// channels) plus its sample rate and channel count, upconverting integer PCM the same way
+/// `generate_wav_data` writes it back out.
+fn read_wav_samples<R: std::io::Read>(reader: R) -> Result<(Vec<f32>, SampleRate, ChannelCount)> {
+    let mut wav_reader = hound::WavReader::new(reader).context("Failed to parse WAV header")?;
+    let spec = wav_reader.spec();
+    let data = match spec.sample_format {
+        hound::SampleFormat::Float => wav_reader
+            .samples::<f32>()
+            .collect::<std::result::Result<Vec<f32>, _>>()
+            .context("Failed to read float WAV samples")?,
+        hound::SampleFormat::Int => {
+            let max_amplitude = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            wav_reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / max_amplitude))
+                .collect::<std::result::Result<Vec<f32>, _>>()
+                .context("Failed to read integer WAV samples")?
+        }
+    };
+    Ok((data, SampleRate(spec.sample_rate), spec.channels))
+}
+
 pub fn list_transcript_paths(config: &AppConfig) -> Result<()> {
     let dir = &config.transcription_results_dir;
     let entries = fs::read_dir(dir).context("Failed to read transcription directory")?;
@@ -140,14 +215,248 @@ pub fn save_transcription_result(
     let dir = config.transcription_results_dir.join(year).join(month);
     fs::create_dir_all(&dir)?;
 
-    let file_path = dir.join(format!("{}.jsonl", day));
+    // The JSONL file remains the source of truth that `list_transcript_paths`
+    // and `show_latest_transcript` read back, regardless of the configured
+    // subtitle format.
+    let jsonl_path = dir.join(format!("{}.jsonl", day));
     let mut file = OpenOptions::new()
         .create(true)
         .append(true)
-        .open(file_path)?;
+        .open(&jsonl_path)?;
 
     let json_line = serde_json::to_string(result)?;
     writeln!(file, "{}", json_line)?;
 
+    match config.transcript_format {
+        TranscriptFormat::Jsonl => {}
+        TranscriptFormat::Srt => {
+            let srt_path = dir.join(format!("{}.srt", day));
+            append_srt_cues(&srt_path, result)?;
+        }
+        TranscriptFormat::Vtt => {
+            let vtt_path = dir.join(format!("{}.vtt", day));
+            append_vtt_cues(&vtt_path, result)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn append_srt_cues(path: &Path, result: &TranscriptionResult) -> Result<()> {
+    let existing_cues = count_srt_cues(path);
+    let offset = last_cue_end_seconds(path, ',', 1);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for (i, segment) in result.segments.iter().enumerate() {
+        let index = existing_cues + i + 1;
+        writeln!(
+            file,
+            "{}\n{} --> {}\n{}\n",
+            index,
+            format_srt_timestamp(offset + segment.start),
+            format_srt_timestamp(offset + segment.end),
+            segment.text
+        )?;
+    }
+    Ok(())
+}
+
+fn append_vtt_cues(path: &Path, result: &TranscriptionResult) -> Result<()> {
+    let needs_header = !path.exists();
+    let offset = last_cue_end_seconds(path, '.', 0);
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_header {
+        writeln!(file, "WEBVTT\n")?;
+    }
+    for segment in &result.segments {
+        writeln!(
+            file,
+            "{} --> {}\n{}\n",
+            format_vtt_timestamp(offset + segment.start),
+            format_vtt_timestamp(offset + segment.end),
+            segment.text
+        )?;
+    }
     Ok(())
 }
+
+fn count_srt_cues(path: &Path) -> usize {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return 0;
+    };
+    contents
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .count()
+}
+
+/// Each `TranscriptionResult`'s segment timestamps are relative to that one
+/// independent transcription, but `append_srt_cues`/`append_vtt_cues` append
+/// into a shared cumulative per-day file. Without a running offset, every
+/// result after the first would restart its cues near 0:00 and overlap
+/// whatever was already written. Recovers that offset from the last cue
+/// already on disk (its end time) rather than tracking it in memory, the
+/// same way `count_srt_cues` recovers the next cue index by reading the
+/// file back — so it stays correct across process restarts too.
+///
+/// `timestamp_line_offset` is the 0-based line within a cue block the
+/// `start --> end` line sits on: 1 for SRT (after the index line), 0 for
+/// VTT (no index line).
+fn last_cue_end_seconds(path: &Path, millis_separator: char, timestamp_line_offset: usize) -> f32 {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return 0.0;
+    };
+    contents
+        .split("\n\n")
+        .map(|block| block.trim())
+        .filter(|block| !block.is_empty() && *block != "WEBVTT")
+        .last()
+        .and_then(|block| block.lines().nth(timestamp_line_offset))
+        .and_then(|line| line.split(" --> ").nth(1))
+        .and_then(|end| parse_timestamp(end.trim(), millis_separator))
+        .unwrap_or(0.0)
+}
+
+/// Inverse of `format_timestamp`.
+fn parse_timestamp(value: &str, millis_separator: char) -> Option<f32> {
+    let (hms, millis) = value.rsplit_once(millis_separator)?;
+    let mut parts = hms.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let secs: u64 = parts.next()?.parse().ok()?;
+    let millis: u64 = millis.parse().ok()?;
+    Some((hours * 3600 + minutes * 60 + secs) as f32 + millis as f32 / 1000.0)
+}
+
+fn format_srt_timestamp(seconds: f32) -> String {
+    format_timestamp(seconds, ',')
+}
+
+fn format_vtt_timestamp(seconds: f32) -> String {
+    format_timestamp(seconds, '.')
+}
+
+fn format_timestamp(seconds: f32, millis_separator: char) -> String {
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let millis = total_millis % 1000;
+    let total_secs = total_millis / 1000;
+    let secs = total_secs % 60;
+    let total_minutes = total_secs / 60;
+    let minutes = total_minutes % 60;
+    let hours = total_minutes / 60;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        hours, minutes, secs, millis_separator, millis
+    )
+}
+
+/// Reads an existing `.jsonl` transcript (one `TranscriptionResult` per line)
+/// and writes the equivalent `.srt` file alongside it, so previously recorded
+/// sessions can be re-exported without re-running transcription.
+pub fn convert_jsonl_to_srt(jsonl_path: &Path) -> Result<PathBuf> {
+    let out_path = jsonl_path.with_extension("srt");
+    if out_path.exists() {
+        fs::remove_file(&out_path)?;
+    }
+    for result in read_jsonl_results(jsonl_path)? {
+        append_srt_cues(&out_path, &result)?;
+    }
+    Ok(out_path)
+}
+
+/// Same as [`convert_jsonl_to_srt`] but emits WebVTT.
+pub fn convert_jsonl_to_vtt(jsonl_path: &Path) -> Result<PathBuf> {
+    let out_path = jsonl_path.with_extension("vtt");
+    if out_path.exists() {
+        fs::remove_file(&out_path)?;
+    }
+    for result in read_jsonl_results(jsonl_path)? {
+        append_vtt_cues(&out_path, &result)?;
+    }
+    Ok(out_path)
+}
+
+fn read_jsonl_results(jsonl_path: &Path) -> Result<Vec<TranscriptionResult>> {
+    let file = fs::File::open(jsonl_path).context("Failed to open transcript jsonl")?;
+    std::io::BufReader::new(file)
+        .lines()
+        .filter(|line| !line.as_ref().map(|l| l.trim().is_empty()).unwrap_or(true))
+        .map(|line| {
+            let line = line?;
+            serde_json::from_str(&line).context("Failed to parse transcript line")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_srt_timestamp_pads_and_rounds() {
+        assert_eq!(format_srt_timestamp(0.0), "00:00:00,000");
+        assert_eq!(format_srt_timestamp(61.5), "00:01:01,500");
+        assert_eq!(format_srt_timestamp(3661.234), "01:01:01,234");
+    }
+
+    #[test]
+    fn format_vtt_timestamp_uses_a_dot_separator() {
+        assert_eq!(format_vtt_timestamp(61.5), "00:01:01.500");
+    }
+
+    fn test_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("mic_transcription_tests");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn count_srt_cues_counts_blocks_in_an_existing_file() {
+        let path = test_file("count_srt_cues_counts_blocks_in_an_existing_file.srt");
+        fs::write(
+            &path,
+            "1\n00:00:00,000 --> 00:00:01,000\nhello\n\n2\n00:00:01,000 --> 00:00:02,000\nworld\n\n",
+        )
+        .unwrap();
+        assert_eq!(count_srt_cues(&path), 2);
+    }
+
+    #[test]
+    fn count_srt_cues_is_zero_for_a_missing_file() {
+        let path = test_file("count_srt_cues_is_zero_for_a_missing_file.srt");
+        assert_eq!(count_srt_cues(&path), 0);
+    }
+
+    #[test]
+    fn append_srt_cues_offsets_later_results_by_the_prior_cues_end_time() {
+        let path = test_file("append_srt_cues_offsets_later_results_by_the_prior_cues_end_time.srt");
+
+        let first = TranscriptionResult {
+            segments: vec![TranscriptionResultSegment {
+                text: "first".to_string(),
+                start: 0.0,
+                end: 2.0,
+            }],
+            language: "en".to_string(),
+            captured_at: None,
+        };
+        let second = TranscriptionResult {
+            segments: vec![TranscriptionResultSegment {
+                text: "second".to_string(),
+                start: 0.0,
+                end: 1.0,
+            }],
+            language: "en".to_string(),
+            captured_at: None,
+        };
+        append_srt_cues(&path, &first).unwrap();
+        append_srt_cues(&path, &second).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(
+            contents.contains("00:00:02,000 --> 00:00:03,000"),
+            "expected the second result's cue to be offset by the first's 2s end time, got:\n{contents}"
+        );
+    }
+}
@@ -1,17 +1,25 @@
 // ui.rs
 
-use crate::config::AppConfig;
-use crate::get_config_path;
-use crate::logging::get_logs_path;
+use crate::activity::{run_audit_writer, ActivityEvent};
+use crate::callbacks::{ChatLights, RegisteredCallback, Webhook, WriteJsonLine};
+use crate::config::{AppConfig, PttModifier};
+use crate::discord::{self, DiscordTranscriptCallback};
+use crate::hue::{authenticate_lights, HueAuthState, LightIntentHandler};
+use crate::intents::RegisteredIntentHandler;
+use crate::logging::{get_activity_log_dir, get_logs_path};
 use crate::microphone::{
-    hook_microphones, process_raw_audio, AudioChunk, Microphone, MicrophoneState,
+    hook_microphones, process_raw_audio, AudioChunk, MicResampler, Microphone, MicrophoneState,
+    SAMPLE_RATE,
 };
-use crate::transcription::{
-    save_transcription_result, send_audio_for_transcription, TranscriptionResult,
+use crate::server::{self, ServerState, TranscriptSegmentEvent};
+use crate::transcription::{send_audio_for_transcription, TranscriptionResult};
+use crate::vad::SpectralVad;
+use crate::wake_word::wake_word_heard;
+use crossterm::event::{
+    Event as CEvent, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
 };
-use anyhow::Context;
-use crossterm::event::{Event as CEvent, EventStream, KeyCode, KeyEvent, KeyEventKind};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement};
 use futures::{FutureExt, StreamExt};
 use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Constraint, Direction, Layout};
@@ -19,100 +27,226 @@ use ratatui::style::{Color, Style};
 use ratatui::text::Span;
 use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Tabs};
 use ratatui::{DefaultTerminal, Frame, Terminal};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io;
+use std::path::PathBuf;
 use std::time::Duration;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 use tracing::{debug, error, info, warn};
 
-#[derive(Eq, PartialEq, Debug, Clone, Copy)]
-enum TranscriptionCallback {
-    WriteJsonLine,
-    ChatLights,
+/// A held key + modifier combination, as observed by whatever is feeding
+/// `AppState`'s push-to-talk signal channel.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PttKeyChord {
+    pub code: char,
+    pub modifiers: KeyModifiers,
 }
 
-enum HueAuthState {
-    Unauthenticated,
-    AwaitingButtonPress,
-    Authenticated,
+/// Fed into `AppState::ptt_signal_sender` whenever a push-to-talk chord is
+/// pressed or released. The crossterm `EventStream` loop below feeds this
+/// while the terminal has focus; a background OS-level global hotkey
+/// listener could feed the same channel (via a clone of the sender) to make
+/// push-to-talk work while the terminal doesn't.
+pub enum PttSignal {
+    KeyDown(PttKeyChord),
+    KeyUp(PttKeyChord),
+}
+
+/// The `KeyModifiers` a `PttModifier` config value corresponds to.
+fn ptt_modifier_to_crossterm(modifier: Option<PttModifier>) -> KeyModifiers {
+    match modifier {
+        None => KeyModifiers::NONE,
+        Some(PttModifier::Shift) => KeyModifiers::SHIFT,
+        Some(PttModifier::Control) => KeyModifiers::CONTROL,
+        Some(PttModifier::Alt) => KeyModifiers::ALT,
+    }
 }
 
 pub struct AppState {
     pub config: AppConfig,
+    /// Path `config` was actually loaded from (may be a project-local
+    /// `.mic.json`/`mic.json` or an explicit `--config-path`, not just the
+    /// OS-default `ProjectDirs` location). Saves/edits must target this, not
+    /// a freshly re-derived default path.
+    pub config_path: PathBuf,
     terminal: Option<DefaultTerminal>,
-    transcription_callbacks: Vec<TranscriptionCallback>,
-    activity_log: Vec<String>,
+    transcription_callbacks: Vec<RegisteredCallback>,
+    selected_callback: usize,
+    activity_log: Vec<ActivityEvent>,
     hue_auth_state: HueAuthState,
-    log_sender: UnboundedSender<String>,
-    log_receiver: UnboundedReceiver<String>,
+    activity_tx: broadcast::Sender<ActivityEvent>,
+    activity_rx: broadcast::Receiver<ActivityEvent>,
     pub raw_audio_sender: UnboundedSender<AudioChunk>,
     raw_audio_receiver: UnboundedReceiver<AudioChunk>,
     pub batch_audio_sender: UnboundedSender<AudioChunk>,
     batch_audio_receiver: UnboundedReceiver<AudioChunk>,
+    /// Where `WaitingForWakeWord`/`WakeWordActivated` mics send candidate
+    /// utterances for a wake-word check instead of straight transcription.
+    pub wake_word_sender: UnboundedSender<AudioChunk>,
+    wake_word_receiver: UnboundedReceiver<AudioChunk>,
     pub transcription_sender: UnboundedSender<TranscriptionResult>,
     transcription_receiver: UnboundedReceiver<TranscriptionResult>,
     microphones: HashMap<String, Microphone>,
+    /// Insertion order of `microphones`, so the list can be cycled through
+    /// with a stable, predictable ordering (`HashMap` has none of its own).
+    microphone_order: Vec<String>,
+    selected_microphone: usize,
+    pub server_state: std::sync::Arc<ServerState>,
+    pub ptt_signal_sender: UnboundedSender<PttSignal>,
+    ptt_signal_receiver: UnboundedReceiver<PttSignal>,
+    /// Push-to-talk chords currently held down, updated from `ptt_signal_receiver`.
+    held_ptt_chords: std::collections::HashSet<PttKeyChord>,
 }
 impl AppState {
-    fn new(config: AppConfig, terminal: DefaultTerminal) -> AppState {
-        let (log_sender, log_receiver) = unbounded_channel::<String>();
+    fn new(config: AppConfig, config_path: PathBuf, terminal: DefaultTerminal) -> AppState {
+        let (activity_tx, activity_rx) = broadcast::channel::<ActivityEvent>(256);
         let (transcription_sender, transcription_receiver) =
             unbounded_channel::<TranscriptionResult>();
         let (raw_audio_sender, raw_audio_receiver) = unbounded_channel::<AudioChunk>();
         let (batch_audio_sender, batch_audio_receiver) = unbounded_channel::<AudioChunk>();
+        let (wake_word_sender, wake_word_receiver) = unbounded_channel::<AudioChunk>();
+        let (ptt_signal_sender, ptt_signal_receiver) = unbounded_channel::<PttSignal>();
+
+        let intent_handler = if config.hue_intent_enabled {
+            RegisteredIntentHandler::enabled(Box::new(LightIntentHandler))
+        } else {
+            RegisteredIntentHandler::disabled(Box::new(LightIntentHandler))
+        };
+        let mut transcription_callbacks: Vec<RegisteredCallback> = vec![
+            RegisteredCallback::enabled(Box::new(WriteJsonLine)),
+            RegisteredCallback::disabled(Box::new(ChatLights {
+                handlers: vec![intent_handler],
+            })),
+        ];
+        if let Some(url) = config.webhook_url.clone() {
+            transcription_callbacks.push(RegisteredCallback::enabled(Box::new(Webhook { url })));
+        }
+        if let (Some(token), Some(text_channel_id)) =
+            (config.discord_token.clone(), config.discord_text_channel_id)
+        {
+            transcription_callbacks.push(RegisteredCallback::enabled(Box::new(
+                DiscordTranscriptCallback {
+                    token,
+                    text_channel_id,
+                },
+            )));
+        }
 
         AppState {
             config,
+            config_path,
             terminal: Some(terminal),
-            transcription_callbacks: vec![TranscriptionCallback::WriteJsonLine],
+            transcription_callbacks,
+            selected_callback: 0,
             activity_log: Vec::new(),
             hue_auth_state: HueAuthState::Unauthenticated,
-            log_sender,
-            log_receiver,
+            activity_tx,
+            activity_rx,
             raw_audio_sender,
             raw_audio_receiver,
             batch_audio_sender,
             batch_audio_receiver,
+            wake_word_sender,
+            wake_word_receiver,
             transcription_sender,
             transcription_receiver,
             microphones: HashMap::default(),
-        }
-    }
-    async fn light_list(&self) -> String {
-        match fetch_lights(&self.config).await {
-            Ok(lights) => lights
-                .iter()
-                .map(|(id, name)| format!("{}: {}", id, name))
-                .collect::<Vec<String>>()
-                .join("\n"),
-            Err(e) => {
-                self.push_activity_log(format!("Error fetching lights: {}", e));
-                "".to_string()
-            }
+            microphone_order: Vec::new(),
+            selected_microphone: 0,
+            server_state: ServerState::new(),
+            ptt_signal_sender,
+            ptt_signal_receiver,
+            held_ptt_chords: std::collections::HashSet::new(),
         }
     }
     pub fn push_activity_log(&self, entry: impl AsRef<str>) {
-        if let Err(e) = self.log_sender.send(entry.as_ref().to_owned()) {
-            error!("Error sending log entry: {}", e);
-        };
+        self.push_event(ActivityEvent::Message {
+            text: entry.as_ref().to_owned(),
+        });
+    }
+    pub fn push_event(&self, event: ActivityEvent) {
+        // An error here just means nobody (UI, audit writer) is listening.
+        let _ = self.activity_tx.send(event);
     }
     pub fn add_microphone(&mut self, mic: Microphone) {
+        if !self.microphones.contains_key(&mic.name) {
+            self.microphone_order.push(mic.name.clone());
+        }
         self.microphones.insert(mic.name.clone(), mic);
     }
+    /// Arms every enabled mic to wait for the configured wake word instead
+    /// of whatever state `hook_microphones` put it in, for `Commands::Listen`.
+    pub fn arm_all_for_wake_word(&mut self) {
+        for mic in self.microphones.values_mut() {
+            if !matches!(mic.state, MicrophoneState::Disabled) {
+                mic.state = MicrophoneState::WaitingForWakeWord;
+            }
+        }
+    }
+    /// Persists the selected microphone's current enabled/muted/gain state
+    /// into `config.microphones` so it survives a restart.
+    fn persist_selected_microphone_config(&mut self) {
+        let Some(name) = self.microphone_order.get(self.selected_microphone).cloned() else {
+            return;
+        };
+        let Some(mic) = self.microphones.get(&name) else {
+            return;
+        };
+        let enabled = !matches!(mic.state, MicrophoneState::Disabled);
+        let entry = self.config.microphones.entry(name).or_default();
+        entry.enabled = enabled;
+        entry.muted = mic.muted;
+        entry.gain = mic.gain;
+        if let Err(e) = self.config.save(&self.config_path) {
+            error!("Failed to save config: {}", e);
+        }
+    }
 }
 
-pub async fn run_app(config: AppConfig) -> anyhow::Result<()> {
-    // Setup terminal
+/// Enables raw mode and the alternate screen, additionally opting into
+/// `REPORT_EVENT_TYPES` when the terminal supports it so push-to-talk can
+/// see `KeyEventKind::Release` events. Without that opt-in most terminals
+/// only ever emit `Press`, so a PTT key latches on forever instead of
+/// releasing when let go.
+fn setup_terminal() -> anyhow::Result<DefaultTerminal> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     crossterm::execute!(stdout, crossterm::terminal::EnterAlternateScreen)?;
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        crossterm::execute!(
+            stdout,
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
     let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
+    Ok(Terminal::new(backend)?)
+}
+
+/// Undoes [`setup_terminal`]: pops the keyboard enhancement flags (if they
+/// were pushed), disables raw mode, and leaves the alternate screen.
+fn teardown_terminal(terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+    if supports_keyboard_enhancement().unwrap_or(false) {
+        crossterm::execute!(terminal.backend_mut(), PopKeyboardEnhancementFlags)?;
+    }
+    disable_raw_mode()?;
+    crossterm::execute!(
+        terminal.backend_mut(),
+        crossterm::terminal::LeaveAlternateScreen
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+pub async fn run_app(
+    config: AppConfig,
+    config_path: PathBuf,
+    only_microphone: Option<String>,
+) -> anyhow::Result<()> {
+    let terminal = setup_terminal()?;
 
     // Create app state
-    let mut app_state = AppState::new(config, terminal);
+    let mut app_state = AppState::new(config, config_path, terminal);
 
     // Restore hue authentication
     if app_state.config.hue_username.is_some() {
@@ -120,23 +254,147 @@ pub async fn run_app(config: AppConfig) -> anyhow::Result<()> {
     };
 
     // Start microphones
-    hook_microphones(&mut app_state)?;
+    hook_microphones(&mut app_state, only_microphone.as_deref())?;
+
+    // Serve recent/live transcription results to other local programs.
+    match app_state.config.serve_address.parse() {
+        Ok(addr) => {
+            tokio::spawn(server::serve(addr, app_state.server_state.clone()));
+        }
+        Err(e) => error!(
+            "Invalid serve_address {:?}: {}",
+            app_state.config.serve_address, e
+        ),
+    }
+
+    // Persist every activity event to a rotating JSONL audit log,
+    // independent of whatever the UI does with its own subscription.
+    match get_activity_log_dir() {
+        Ok(dir) => {
+            tokio::spawn(run_audit_writer(app_state.activity_tx.subscribe(), dir));
+        }
+        Err(e) => error!("Could not determine activity log directory: {}", e),
+    }
+
+    // Treat a configured Discord voice channel as another audio source,
+    // feeding the same raw_audio_sender local microphones use.
+    if let (Some(token), Some(guild_id), Some(voice_channel_id)) = (
+        app_state.config.discord_token.clone(),
+        app_state.config.discord_guild_id,
+        app_state.config.discord_voice_channel_id,
+    ) {
+        let raw_audio_sender = app_state.raw_audio_sender.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                discord::join_voice_channel(&token, guild_id, voice_channel_id, raw_audio_sender)
+                    .await
+            {
+                error!("Discord voice integration failed: {}", e);
+            }
+        });
+    }
 
     // Main loop
     let res = run_ui(&mut app_state).await;
 
     let mut terminal = app_state.terminal.take().unwrap();
-    // Restore terminal
-    disable_raw_mode()?;
-    crossterm::execute!(
-        terminal.backend_mut(),
-        crossterm::terminal::LeaveAlternateScreen
-    )?;
-    terminal.show_cursor()?;
+    teardown_terminal(&mut terminal)?;
 
     res
 }
 
+/// `Commands::Listen`'s entry point: identical setup to `run_app`, except
+/// every mic starts in `WaitingForWakeWord` instead of `WaitingForVoiceActivity`,
+/// so dictation only opens once the configured wake word has been heard.
+pub async fn run_listen_mode(
+    config: AppConfig,
+    config_path: PathBuf,
+    only_microphone: Option<String>,
+) -> anyhow::Result<()> {
+    if config.wake_word.is_none() {
+        anyhow::bail!("No `wake_word` configured; set one before using `mic listen`");
+    }
+
+    let terminal = setup_terminal()?;
+
+    let mut app_state = AppState::new(config, config_path, terminal);
+
+    if app_state.config.hue_username.is_some() {
+        app_state.hue_auth_state = HueAuthState::Authenticated;
+    };
+
+    hook_microphones(&mut app_state, only_microphone.as_deref())?;
+    app_state.arm_all_for_wake_word();
+
+    match app_state.config.serve_address.parse() {
+        Ok(addr) => {
+            tokio::spawn(server::serve(addr, app_state.server_state.clone()));
+        }
+        Err(e) => error!(
+            "Invalid serve_address {:?}: {}",
+            app_state.config.serve_address, e
+        ),
+    }
+
+    match get_activity_log_dir() {
+        Ok(dir) => {
+            tokio::spawn(run_audit_writer(app_state.activity_tx.subscribe(), dir));
+        }
+        Err(e) => error!("Could not determine activity log directory: {}", e),
+    }
+
+    if let (Some(token), Some(guild_id), Some(voice_channel_id)) = (
+        app_state.config.discord_token.clone(),
+        app_state.config.discord_guild_id,
+        app_state.config.discord_voice_channel_id,
+    ) {
+        let raw_audio_sender = app_state.raw_audio_sender.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                discord::join_voice_channel(&token, guild_id, voice_channel_id, raw_audio_sender)
+                    .await
+            {
+                error!("Discord voice integration failed: {}", e);
+            }
+        });
+    }
+
+    let res = run_ui(&mut app_state).await;
+
+    let mut terminal = app_state.terminal.take().unwrap();
+    teardown_terminal(&mut terminal)?;
+
+    res
+}
+
+/// Transcribes a flushed `WakeWordActivated` candidate and, if the
+/// configured wake word was heard in it, arms that mic for a real dictation
+/// session instead of going back to `WaitingForWakeWord`.
+async fn process_wake_word_candidate(
+    app_state: &mut AppState,
+    candidate: AudioChunk,
+) -> anyhow::Result<()> {
+    let Some(wake_word) = app_state.config.wake_word.clone() else {
+        return Ok(());
+    };
+    let mic_name = candidate.mic_name.clone();
+    let result =
+        send_audio_for_transcription(&app_state.config.transcription_api_url, &candidate)?;
+    let wake_phonetic = app_state.config.wake_phonetic.clone();
+    let heard = result
+        .segments
+        .iter()
+        .any(|segment| wake_word_heard(&segment.text, &wake_word, wake_phonetic.as_deref()));
+    if heard {
+        info!("Wake word heard from mic {}", mic_name);
+        app_state.push_activity_log(format!("Wake word heard from mic {}", mic_name));
+        if let Some(mic) = app_state.microphones.get_mut(&mic_name) {
+            mic.state = MicrophoneState::WaitingForVoiceActivity;
+        }
+    }
+    Ok(())
+}
+
 async fn process_batch_audio(
     app_state: &mut AppState,
     audio_data: AudioChunk,
@@ -145,16 +403,23 @@ async fn process_batch_audio(
         "Received audio data for transcription, got {} samples",
         audio_data.data.len()
     );
-    match send_audio_for_transcription(&app_state.config.transcription_api_url, audio_data).await {
-        Ok(result) => {
-            let timestamp = chrono::Local::now();
-            if let Err(e) = save_transcription_result(&app_state.config, &result, timestamp) {
-                error!("Failed to save transcription: {}", e);
-            }
-
+    let mic_name = audio_data.mic_name.clone();
+    let captured_at = audio_data.captured_at;
+    match send_audio_for_transcription(&app_state.config.transcription_api_url, &audio_data) {
+        Ok(mut result) => {
+            result.captured_at = Some(captured_at);
+            let timestamp = captured_at;
             for segment in &result.segments {
                 info!("Heard \"{}\"", segment.text);
-                app_state.push_activity_log(format!("Heard \"{}\"", segment.text));
+                app_state.push_event(ActivityEvent::TranscriptionHeard {
+                    mic: mic_name.clone(),
+                    text: segment.text.clone(),
+                });
+                app_state.server_state.publish(TranscriptSegmentEvent {
+                    mic_name: mic_name.clone(),
+                    timestamp,
+                    text: segment.text.clone(),
+                });
             }
             app_state.transcription_sender.send(result)?;
         }
@@ -179,6 +444,7 @@ async fn run_ui(mut app_state: &mut AppState) -> anyhow::Result<()> {
         let crossterm_event = crossterm_event_stream.next().fuse();
         let raw_audio_chunk = app_state.raw_audio_receiver.recv();
         let batch_audio_chunk = app_state.batch_audio_receiver.recv();
+        let wake_word_candidate = app_state.wake_word_receiver.recv();
         tokio::select! {
             _ = delay => {
                 // this branch ensures the UI redraws frequently
@@ -187,6 +453,22 @@ async fn run_ui(mut app_state: &mut AppState) -> anyhow::Result<()> {
                 match maybe_event {
                     Some(Ok(event)) => {
                         if let CEvent::Key(key) = event {
+                            // Feed push-to-talk chord state regardless of
+                            // `kind`, since releases matter here too.
+                            if let KeyCode::Char(code) = key.code {
+                                let chord = PttKeyChord {
+                                    code,
+                                    modifiers: key.modifiers,
+                                };
+                                let signal = match key.kind {
+                                    KeyEventKind::Press => Some(PttSignal::KeyDown(chord)),
+                                    KeyEventKind::Release => Some(PttSignal::KeyUp(chord)),
+                                    KeyEventKind::Repeat => None,
+                                };
+                                if let Some(signal) = signal {
+                                    let _ = app_state.ptt_signal_sender.send(signal);
+                                }
+                            }
                             if key.kind != KeyEventKind::Press {
                                 continue;
                             }
@@ -205,18 +487,81 @@ async fn run_ui(mut app_state: &mut AppState) -> anyhow::Result<()> {
                     None => {}
                 }
             }
-            Some(log) = app_state.log_receiver.recv() => {
-                app_state.activity_log.push(log);
+            Some(signal) = app_state.ptt_signal_receiver.recv() => {
+                match signal {
+                    PttSignal::KeyDown(chord) => {
+                        app_state.held_ptt_chords.insert(chord);
+                    }
+                    PttSignal::KeyUp(chord) => {
+                        app_state.held_ptt_chords.remove(&chord);
+                    }
+                }
+            }
+            activity_event = app_state.activity_rx.recv() => {
+                match activity_event {
+                    Ok(event) => app_state.activity_log.push(event),
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        warn!("Activity log UI buffer lagged, dropped {} events", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {}
+                }
             }
             Some(transcription) = app_state.transcription_receiver.recv() => {
                 handle_transcription_result(&mut app_state, transcription).await?;
             }
             Some(chunk) = raw_audio_chunk => {
-                if let Some(mic) = app_state.microphones.get_mut(&chunk.mic_name) {
-                    process_raw_audio(chunk, &mut mic.state, &app_state.batch_audio_sender);
-                } else {
-                    warn!("Received audio chunk for unknown mic: {}", chunk.mic_name);
+                // Audio sources that aren't hooked up ahead of time (e.g. a
+                // Discord speaker keyed by SSRC) register themselves here on
+                // first chunk, the same way local mics are pre-registered by
+                // `hook_microphones`.
+                if !app_state.microphones.contains_key(&chunk.mic_name) {
+                    info!("Registering new microphone: {}", chunk.mic_name);
+                    app_state.microphone_order.push(chunk.mic_name.clone());
+                    // Same reasoning as `hook_microphone`: only build a
+                    // resampler when the source doesn't already run at
+                    // `SAMPLE_RATE`, so e.g. Discord's 48kHz stereo Opus
+                    // output actually reaches VAD/transcription at 16kHz
+                    // instead of native rate.
+                    let resampler = if chunk.sample_rate != SAMPLE_RATE {
+                        Some(MicResampler::new(chunk.sample_rate.0, SAMPLE_RATE.0)?)
+                    } else {
+                        None
+                    };
+                    app_state.microphones.insert(
+                        chunk.mic_name.clone(),
+                        Microphone {
+                            name: chunk.mic_name.clone(),
+                            state: MicrophoneState::WaitingForVoiceActivity,
+                            stream: None,
+                            muted: false,
+                            gain: 100,
+                            vad: SpectralVad::new(),
+                            push_to_talk_key: None,
+                            push_to_talk_modifier: None,
+                            expected_sample_index: None,
+                            resampler,
+                        },
+                    );
                 }
+                let mic = app_state.microphones.get_mut(&chunk.mic_name).unwrap();
+                let ptt_active = mic.push_to_talk_key.is_some_and(|key| {
+                    let chord = PttKeyChord {
+                        code: key,
+                        modifiers: ptt_modifier_to_crossterm(mic.push_to_talk_modifier),
+                    };
+                    app_state.held_ptt_chords.contains(&chord)
+                });
+                let wake_silence_timeout = Duration::from_secs(
+                    app_state.config.wake_silence_timeout_secs.max(1) as u64,
+                );
+                process_raw_audio(
+                    chunk,
+                    mic,
+                    &app_state.batch_audio_sender,
+                    &app_state.wake_word_sender,
+                    wake_silence_timeout,
+                    ptt_active,
+                );
             }
             Some(chunk) = batch_audio_chunk => {
                 if let Err(e) = process_batch_audio(&mut app_state, chunk).await {
@@ -224,6 +569,12 @@ async fn run_ui(mut app_state: &mut AppState) -> anyhow::Result<()> {
                     app_state.push_activity_log(format!("Error handling audio data: {}", e));
                 };
             }
+            Some(candidate) = wake_word_candidate => {
+                if let Err(e) = process_wake_word_candidate(&mut app_state, candidate).await {
+                    error!("Error checking wake word candidate: {}", e);
+                    app_state.push_activity_log(format!("Error checking wake word candidate: {}", e));
+                };
+            }
         }
     }
 }
@@ -232,16 +583,21 @@ async fn handle_transcription_result(
     app_state: &mut AppState,
     transcription: TranscriptionResult,
 ) -> anyhow::Result<()> {
-    let chat_light = app_state
-        .transcription_callbacks
-        .contains(&TranscriptionCallback::ChatLights);
-    for segment in &transcription.segments {
-        if chat_light {
-            if let Err(e) = handle_hue_llm_voice_commands(&app_state, &segment.text).await {
-                app_state.push_activity_log(format!("Error processing ChatLights: {}", e));
-            }
+    // Take the registry out so callbacks can borrow `app_state` immutably
+    // while we run them, then put it back once they've all finished.
+    let callbacks = std::mem::take(&mut app_state.transcription_callbacks);
+    for registered in &callbacks {
+        if !registered.enabled {
+            continue;
+        }
+        if let Err(e) = registered.callback.on_transcription(app_state, &transcription).await {
+            app_state.push_event(ActivityEvent::CallbackError {
+                name: registered.callback.name().to_string(),
+                error: e.to_string(),
+            });
         }
     }
+    app_state.transcription_callbacks = callbacks;
     Ok(())
 }
 
@@ -263,11 +619,12 @@ async fn handle_key_event(
         KeyCode::Char(x) if x == app_state.config.key_config.edit_config => {
             edit_config(
                 &app_state.config.config_editor,
+                &app_state.config_path,
                 &mut app_state.terminal.as_mut().unwrap(),
             )?;
         }
         KeyCode::Char(x) if x == app_state.config.key_config.open_config => {
-            open_config(&app_state.config.big_config_editor)?;
+            open_config(&app_state.config.big_config_editor, &app_state.config_path)?;
         }
         KeyCode::Char(x) if x == app_state.config.key_config.open_logs => {
             open_logs(&app_state.config)?;
@@ -275,8 +632,26 @@ async fn handle_key_event(
         KeyCode::Char(x) if x == app_state.config.key_config.authenticate_lights => {
             authenticate_lights(app_state).await?;
         }
-        KeyCode::Char(x) if x == app_state.config.key_config.toggle_chat_lights => {
-            toggle_chat_lights_callback(app_state);
+        KeyCode::Char(x) if x == app_state.config.key_config.mic_cycle_mode => {
+            cycle_selected_microphone(app_state);
+        }
+        KeyCode::Char(x) if x == app_state.config.key_config.mic_toggle_disabled => {
+            toggle_selected_microphone_disabled(app_state);
+        }
+        KeyCode::Char(x) if x == app_state.config.key_config.mic_mute_toggle => {
+            toggle_selected_microphone_mute(app_state);
+        }
+        KeyCode::Char(x) if x == app_state.config.key_config.mic_gain_up => {
+            adjust_selected_microphone_gain(app_state, 5);
+        }
+        KeyCode::Char(x) if x == app_state.config.key_config.mic_gain_down => {
+            adjust_selected_microphone_gain(app_state, -5);
+        }
+        KeyCode::Char(x) if x == app_state.config.key_config.callback_cycle_selection => {
+            cycle_selected_callback(app_state);
+        }
+        KeyCode::Char(x) if x == app_state.config.key_config.callback_toggle_selected => {
+            toggle_selected_callback(app_state);
         }
         _ => {}
     }
@@ -303,9 +678,11 @@ fn ui(f: &mut Frame, app_state: &AppState) {
 
     // Microphone list
     let mic_items: Vec<ListItem> = app_state
-        .microphones
-        .values()
-        .map(|mic| {
+        .microphone_order
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| app_state.microphones.get(name).map(|mic| (i, mic)))
+        .map(|(i, mic)| {
             let status = match &mic.state {
                 MicrophoneState::Disabled => "DISABLED".to_string(),
                 MicrophoneState::WaitingForPushToTalk => "IDLE - WaitingForPushToTalk".to_string(),
@@ -315,10 +692,31 @@ fn ui(f: &mut Frame, app_state: &AppState) {
                 MicrophoneState::VoiceActivated(active_state) => {
                     format!("LISTENING - Samples: {}", active_state.data_so_far.len())
                 }
-                _ => "UNKNOWN".to_string(),
+                MicrophoneState::PushToTalkActivated(active_state) => {
+                    format!(
+                        "PUSH-TO-TALK - Samples: {}",
+                        active_state.data_so_far.len()
+                    )
+                }
+                MicrophoneState::WaitingForWakeWord => "IDLE - WaitingForWakeWord".to_string(),
+                MicrophoneState::WakeWordActivated(active_state) => {
+                    format!(
+                        "LISTENING FOR WAKE WORD - Samples: {}",
+                        active_state.data_so_far.len()
+                    )
+                }
             };
-
-            ListItem::new(Span::raw(format!("{} | {}", mic.name, status)))
+            let mute_flag = if mic.muted { " MUTED" } else { "" };
+            let line = format!(
+                "{} | {} | gain {}{}",
+                mic.name, status, mic.gain, mute_flag
+            );
+            let style = if i == app_state.selected_microphone {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Span::styled(line, style))
         })
         .collect();
 
@@ -353,7 +751,7 @@ fn ui(f: &mut Frame, app_state: &AppState) {
         .activity_log
         .iter()
         .rev()
-        .map(|entry| ListItem::new(entry.clone()))
+        .map(|entry| ListItem::new(entry.summary()))
         .collect();
 
     let log_list =
@@ -361,105 +759,31 @@ fn ui(f: &mut Frame, app_state: &AppState) {
 
     f.render_widget(log_list, chunks[2]);
 
-    // Callbacks
-    let callback_titles: Vec<&str> = app_state
+    // Callbacks: dim disabled entries, select the cycle-highlighted one.
+    let callback_titles: Vec<Span> = app_state
         .transcription_callbacks
         .iter()
-        .map(|callback| match callback {
-            TranscriptionCallback::WriteJsonLine => "WriteJsonLine",
-            TranscriptionCallback::ChatLights => "ChatLights",
+        .map(|registered| {
+            let style = if registered.enabled {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Span::styled(registered.callback.name().to_string(), style)
         })
         .collect();
 
-    let tabs = Tabs::new(callback_titles.iter().cloned().map(Span::from))
+    let tabs = Tabs::new(callback_titles)
         .block(Block::default().borders(Borders::ALL).title("Callbacks"))
-        .style(Style::default().fg(Color::Cyan));
+        .select(app_state.selected_callback)
+        .highlight_style(Style::default().fg(Color::Yellow));
 
     f.render_widget(tabs, chunks[3]);
 }
 
-async fn authenticate_lights(app_state: &mut AppState) -> anyhow::Result<()> {
-    // only proceed if not already authenticated
-    if let HueAuthState::Authenticated { .. } = app_state.hue_auth_state {
-        app_state.push_activity_log("Already authenticated with Hue bridge.");
-        return Ok(());
-    }
-
-    let bridge_ip = &app_state.config.hue_bridge_ip;
-    if bridge_ip.is_empty() {
-        app_state.push_activity_log("Hue bridge IP not set in config.");
-        return Ok(());
-    }
-
-    let url = format!("https://{}/api", bridge_ip);
-
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-
-    let response = client
-        .post(&url)
-        .json(&serde_json::json!({"devicetype": "mic_app#rust"}))
-        .send()
-        .await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-
-    let result = response_json
-        .as_array()
-        .ok_or_else(|| anyhow::anyhow!("Unexpected response"))?;
-
-    if result.is_empty() {
-        anyhow::bail!("Empty response from Hue bridge");
-    }
-
-    let first_item = &result[0];
-
-    if let Some(success) = first_item.get("success") {
-        let username = success
-            .get("username")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow::anyhow!("No username in success response"))?
-            .to_string();
-
-        app_state.config.hue_username = Some(username.clone());
-        app_state.hue_auth_state = HueAuthState::Authenticated;
-
-        // Save the config with the new username
-        let config_path = get_config_path()?;
-        app_state.config.save(&config_path)?;
-
-        app_state.push_activity_log("Successfully authenticated with Hue bridge.");
-    } else if let Some(error) = first_item.get("error") {
-        let error_type = error.get("type").and_then(|v| v.as_i64()).unwrap_or(0);
-        let description = error
-            .get("description")
-            .and_then(|v| v.as_str())
-            .unwrap_or("");
-        if error_type == 101 {
-            // link button not pressed
-            app_state.hue_auth_state = HueAuthState::AwaitingButtonPress;
-
-            app_state.push_activity_log("Please press the link button on the Hue bridge.");
-        } else {
-            app_state.push_activity_log(format!(
-                "Error authenticating with Hue bridge: {}",
-                description
-            ));
-        }
-    } else {
-        app_state.push_activity_log("Unknown response from Hue bridge.");
-    }
-
-    Ok(())
-}
-
-fn edit_config(editor: &str, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+fn edit_config(editor: &str, config_path: &PathBuf, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
     use std::process::Command;
 
-    // Get config path
-    let config_path = get_config_path()?;
-
     // Restore terminal
     disable_raw_mode()?;
     crossterm::execute!(io::stdout(), crossterm::terminal::LeaveAlternateScreen)?;
@@ -479,10 +803,9 @@ fn edit_config(editor: &str, terminal: &mut DefaultTerminal) -> anyhow::Result<(
     Ok(())
 }
 
-fn open_config(editor: &str) -> anyhow::Result<()> {
+fn open_config(editor: &str, config_path: &PathBuf) -> anyhow::Result<()> {
     use std::process::Command;
-    let config_path = get_config_path()?;
-    let status = Command::new(editor).arg(&config_path).status()?;
+    let status = Command::new(editor).arg(config_path).status()?;
     if status.success() {
         info!("Config opened successfully");
     } else {
@@ -509,227 +832,99 @@ fn open_logs(config: &AppConfig) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn toggle_chat_lights_callback(app_state: &mut AppState) {
-    info!("Toggling ChatLights callback");
-    if app_state
-        .transcription_callbacks
-        .contains(&TranscriptionCallback::ChatLights)
-    {
-        app_state
-            .transcription_callbacks
-            .retain(|c| c != &TranscriptionCallback::ChatLights);
-
-        app_state.push_activity_log("Disabled ChatLights callback.".to_string());
-    } else {
-        app_state
-            .transcription_callbacks
-            .push(TranscriptionCallback::ChatLights);
-
-        app_state.push_activity_log("Enabled ChatLights callback.".to_string());
+fn cycle_selected_callback(app_state: &mut AppState) {
+    if app_state.transcription_callbacks.is_empty() {
+        return;
     }
+    app_state.selected_callback =
+        (app_state.selected_callback + 1) % app_state.transcription_callbacks.len();
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LightUpdateResponse {
-    light_updates: Vec<LightUpdate>,
+fn toggle_selected_callback(app_state: &mut AppState) {
+    let Some(registered) = app_state
+        .transcription_callbacks
+        .get_mut(app_state.selected_callback)
+    else {
+        return;
+    };
+    registered.enabled = !registered.enabled;
+    let name = registered.callback.name().to_string();
+    let enabled = registered.enabled;
+    info!("{} callback {}", if enabled { "Enabled" } else { "Disabled" }, name);
+    app_state.push_activity_log(format!(
+        "{} {} callback.",
+        if enabled { "Enabled" } else { "Disabled" },
+        name
+    ));
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct LightUpdate {
-    light_id: u32,
-    red: Option<u8>,    // Red value between 0-255
-    green: Option<u8>,  // Green value between 0-255
-    blue: Option<u8>,   // Blue value between 0-255
-    brightness: Option<u8>, // Brightness between 1-254
-    on: Option<bool>,
-}
-fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (u16, u8, u8) {
-    let r = r as f32 / 255.0;
-    let g = g as f32 / 255.0;
-    let b = b as f32 / 255.0;
-
-    let max = r.max(g.max(b));
-    let min = r.min(g.min(b));
-    let delta = max - min;
-
-    // Hue calculation
-    let mut h = 0.0;
-    if delta != 0.0 {
-        if max == r {
-            h = 60.0 * (((g - b) / delta) % 6.0);
-        } else if max == g {
-            h = 60.0 * (((b - r) / delta) + 2.0);
-        } else if max == b {
-            h = 60.0 * (((r - g) / delta) + 4.0);
-        }
-    }
-    if h < 0.0 {
-        h += 360.0;
+fn cycle_selected_microphone(app_state: &mut AppState) {
+    if app_state.microphone_order.is_empty() {
+        return;
     }
-
-    // Saturation calculation
-    let s = if max == 0.0 { 0.0 } else { delta / max };
-
-    // Value calculation
-    let v = max;
-
-    // Map h from [0,360) to [0,65535]
-    let hue = (h / 360.0 * 65535.0).round() as u16;
-
-    // Map s from [0,1] to [0,254]
-    let sat = (s * 254.0).round() as u8;
-
-    // Map v from [0,1] to [1,254]
-    let bri = (v * 253.0 + 1.0).round() as u8;
-
-    (hue, sat, bri)
+    app_state.selected_microphone =
+        (app_state.selected_microphone + 1) % app_state.microphone_order.len();
 }
 
-async fn handle_hue_llm_voice_commands(app_state: &AppState, transcript: &str) -> anyhow::Result<()> {
-    info!("Processing ChatLights for \"{}\"", transcript);
-    let client = Client::new();
-    let model_api_url = "http://localhost:11434/api/generate"; // TODO: make config variable
-    let model = "x/llama3.2-vision"; // TODO: make config variable
-
-    // Build the prompt
-    let prompt = format!(
-        r#"
-You are a light controlling robot.
-Your job is to detect when a user is instructing you to change the lights.
-
-{}
-
-
-Your output should have the following structure.
-{{
-    "light_updates": [ {{
-        "light_id": number,
-        "red": number (0-255),
-        "green": number (0-255),
-        "blue": number (0-255),
-        "brightness": number (1-254),
-        "on": bool
-    }} ]
-}}
-
-If it seems like the user is not talking to the robot, then an empty array should be returned for the "light_updates" property.
-
-Transcript:
-"{}"
-
-Respond only with the JSON output.
-"#,
-        app_state.light_list().await, // We'll implement this method
-        transcript
-    );
-
-    // Send the request
-    let response = client
-        .post(model_api_url)
-        .json(&serde_json::json!({
-            "model": model,
-            "prompt": prompt,
-            "stream": false,
-        }))
-        .send().await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-    let generated_text = response_json
-        .get("response")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("No response from model"))?;
-
-    // Parse the generated text as JSON
-    let light_updates: LightUpdateResponse = serde_json::from_str(generated_text.trim())
-        .context("Failed to parse model response as JSON")?;
-
-    // Process the light updates
-    if !light_updates.light_updates.is_empty() {
-        // Send commands to the Hue bridge
-        for update in light_updates.light_updates {
-            send_hue_command(app_state, update).await?;
+fn toggle_selected_microphone_disabled(app_state: &mut AppState) {
+    let Some(name) = app_state
+        .microphone_order
+        .get(app_state.selected_microphone)
+        .cloned()
+    else {
+        return;
+    };
+    let Some(mic) = app_state.microphones.get_mut(&name) else {
+        return;
+    };
+    let from = format!("{:?}", mic.state);
+    mic.state = match mic.state {
+        MicrophoneState::Disabled if mic.push_to_talk_key.is_some() => {
+            MicrophoneState::WaitingForPushToTalk
         }
-    }
-
-    Ok(())
+        MicrophoneState::Disabled => MicrophoneState::WaitingForVoiceActivity,
+        _ => MicrophoneState::Disabled,
+    };
+    let to = format!("{:?}", mic.state);
+    app_state.push_event(ActivityEvent::MicStateChanged { mic: name, from, to });
+    app_state.persist_selected_microphone_config();
 }
 
-async fn send_hue_command(app_state: &AppState, update: LightUpdate) -> anyhow::Result<()> {
-    let bridge_ip = &app_state.config.hue_bridge_ip;
-    let username = match &app_state.config.hue_username {
-        Some(u) => u,
-        None => {
-            app_state.push_activity_log("Not authenticated with Hue bridge.".to_string());
-            return Ok(());
-        }
+fn toggle_selected_microphone_mute(app_state: &mut AppState) {
+    let Some(name) = app_state
+        .microphone_order
+        .get(app_state.selected_microphone)
+        .cloned()
+    else {
+        return;
     };
-
-    let url = format!(
-        "https://{}/api/{}/lights/{}/state",
-        bridge_ip, username, update.light_id
-    );
-
-    let mut body = serde_json::Map::new();
-
-    if let Some(on) = update.on {
-        body.insert("on".to_string(), serde_json::Value::Bool(on));
-    }
-
-    if let (Some(red), Some(green), Some(blue)) = (update.red, update.green, update.blue) {
-        let (hue, sat, bri) = rgb_to_hsv(red, green, blue);
-        body.insert("hue".to_string(), serde_json::Value::Number(hue.into()));
-        body.insert("sat".to_string(), serde_json::Value::Number(sat.into()));
-        body.insert("bri".to_string(), serde_json::Value::Number(bri.into()));
-    } else if let Some(bri) = update.brightness {
-        body.insert("bri".to_string(), serde_json::Value::Number(bri.into()));
-    }
-
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-
-    let response = client.put(&url).json(&body).send().await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-
+    let Some(mic) = app_state.microphones.get_mut(&name) else {
+        return;
+    };
+    mic.muted = !mic.muted;
+    let muted = mic.muted;
     app_state.push_activity_log(format!(
-        "Sent light command to light {}: {:?}",
-        update.light_id, response_json
+        "{} microphone {}",
+        if muted { "Muted" } else { "Unmuted" },
+        name,
     ));
-
-    Ok(())
+    app_state.persist_selected_microphone_config();
 }
 
-async fn fetch_lights(config: &AppConfig) -> anyhow::Result<HashMap<u32, String>> {
-    let bridge_ip = &config.hue_bridge_ip;
-    let username = match &config.hue_username {
-        Some(u) => u,
-        None => anyhow::bail!("Not authenticated with Hue bridge"),
+fn adjust_selected_microphone_gain(app_state: &mut AppState, delta: i16) {
+    let Some(name) = app_state
+        .microphone_order
+        .get(app_state.selected_microphone)
+        .cloned()
+    else {
+        return;
     };
-
-    let url = format!("https://{}/api/{}/lights", bridge_ip, username);
-
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-
-    let response = client.get(&url).send().await?;
-
-    let response_json: serde_json::Value = response.json().await?;
-
-    let lights = response_json
-        .as_object()
-        .ok_or_else(|| anyhow::anyhow!("Invalid lights response"))?;
-
-    let mut light_map = HashMap::new();
-
-    for (id_str, light_info) in lights {
-        if let Ok(id) = id_str.parse::<u32>() {
-            if let Some(name) = light_info.get("name").and_then(|n| n.as_str()) {
-                light_map.insert(id, name.to_string());
-            }
-        }
-    }
-
-    Ok(light_map)
+    let Some(mic) = app_state.microphones.get_mut(&name) else {
+        return;
+    };
+    let new_gain = (mic.gain as i16 + delta).clamp(0, 100) as u8;
+    mic.gain = new_gain;
+    app_state.push_activity_log(format!("Set {} gain to {}", name, new_gain));
+    app_state.persist_selected_microphone_config();
 }
+
@@ -0,0 +1,163 @@
+// vad.rs
+//
+// Frequency-domain voice activity detector used by `process_raw_audio` in
+// place of the old flat `amplitude > 0.01` threshold, which mis-fired on
+// fan noise and clipped quiet speech onsets. Frames are Hann-windowed,
+// FFT'd, and their speech-band energy compared against a per-microphone
+// adaptive noise floor, so each mic's own room noise doesn't need a
+// hand-tuned constant.
+//
+// `process_raw_audio` downmixes and resamples each chunk to mono
+// `SAMPLE_RATE` before calling in here; frames are still sized from the
+// sample rate passed in (30ms worth of samples) rather than assuming a
+// fixed 16kHz input.
+
+use realfft::RealFftPlanner;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Frame length in seconds; 30ms is the conventional speech-VAD frame size.
+const FRAME_SECONDS: f32 = 0.03;
+/// Speech energy band, matched to the bulk of voiced speech content.
+const SPEECH_BAND_HZ: (f32, f32) = (300.0, 3400.0);
+/// Exponential-average decay applied to the noise floor while idle.
+const NOISE_FLOOR_DECAY: f32 = 0.95;
+/// SNR a frame's band energy must clear, relative to the noise floor, to
+/// count as speech.
+const DEFAULT_SNR_THRESHOLD_DB: f32 = 6.0;
+/// Frames of hangover kept active after SNR drops back below threshold, so
+/// trailing consonants aren't cut off; ~0.45s at a 30ms frame size.
+const DEFAULT_HANGOVER_FRAMES: u32 = 15;
+
+/// FFT plan and derived constants for one sample rate, rebuilt only when the
+/// observed sample rate changes.
+struct FftConfig {
+    sample_rate: u32,
+    frame_size: usize,
+    hann_window: Vec<f32>,
+    fft: Arc<dyn realfft::RealToComplex<f32>>,
+    scratch: Vec<num_complex::Complex<f32>>,
+    band_bins: Range<usize>,
+}
+
+impl FftConfig {
+    fn new(sample_rate: u32) -> Self {
+        let frame_size = ((sample_rate as f32 * FRAME_SECONDS).round() as usize).max(2);
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(frame_size);
+
+        let hann_window = (0..frame_size)
+            .map(|i| {
+                0.5 - 0.5
+                    * (2.0 * std::f32::consts::PI * i as f32 / (frame_size as f32 - 1.0)).cos()
+            })
+            .collect();
+
+        let bin_hz = sample_rate as f32 / frame_size as f32;
+        let max_bin = frame_size / 2 + 1;
+        let band_bins = ((SPEECH_BAND_HZ.0 / bin_hz).floor() as usize).min(max_bin)
+            ..((SPEECH_BAND_HZ.1 / bin_hz).ceil() as usize).min(max_bin);
+
+        FftConfig {
+            sample_rate,
+            frame_size,
+            hann_window,
+            scratch: fft.make_output_vec(),
+            fft,
+            band_bins,
+        }
+    }
+}
+
+/// Adaptive-noise-floor spectral VAD. Each `Microphone` owns one so its
+/// noise floor and hangover counter track that mic's own environment
+/// instead of being reset or shared across mics.
+pub struct SpectralVad {
+    config: Option<FftConfig>,
+    noise_floor: f32,
+    hangover_remaining: u32,
+    snr_threshold_db: f32,
+    hangover_frames: u32,
+    /// Samples carried over from the previous `is_speech` call that didn't
+    /// fill a whole frame yet, mirroring `MicResampler::pending` — dropping
+    /// this tail every call (the old behaviour) could miss a short burst of
+    /// speech that straddled a chunk boundary.
+    pending: Vec<f32>,
+}
+
+impl Default for SpectralVad {
+    fn default() -> Self {
+        SpectralVad {
+            config: None,
+            noise_floor: 0.0,
+            hangover_remaining: 0,
+            snr_threshold_db: DEFAULT_SNR_THRESHOLD_DB,
+            hangover_frames: DEFAULT_HANGOVER_FRAMES,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl SpectralVad {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Slices `samples` (mono, `sample_rate` Hz) into analysis frames and
+    /// reports whether any of them counts as speech, including frames still
+    /// covered by the post-speech hangover. Leftover samples that don't fill
+    /// a whole frame are carried over in `self.pending` and prepended to the
+    /// next call's samples, instead of being dropped.
+    pub fn is_speech(&mut self, samples: &[f32], sample_rate: u32) -> bool {
+        if self.config.as_ref().map(|c| c.sample_rate) != Some(sample_rate) {
+            self.config = Some(FftConfig::new(sample_rate));
+            self.pending.clear();
+        }
+        let Some(config) = self.config.as_mut() else {
+            return false;
+        };
+
+        self.pending.extend_from_slice(samples);
+
+        let mut any_speech = false;
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= config.frame_size {
+            let frame = &self.pending[consumed..consumed + config.frame_size];
+            consumed += config.frame_size;
+
+            let mut windowed: Vec<f32> = frame
+                .iter()
+                .zip(&config.hann_window)
+                .map(|(&s, &w)| s * w)
+                .collect();
+            config
+                .fft
+                .process(&mut windowed, &mut config.scratch)
+                .expect("VAD FFT failed");
+
+            let energy: f32 = config.scratch[config.band_bins.clone()]
+                .iter()
+                .map(|c| c.norm_sqr())
+                .sum();
+
+            let is_speech_frame = self.noise_floor > 0.0
+                && 10.0 * (energy / self.noise_floor).log10() > self.snr_threshold_db;
+
+            if is_speech_frame {
+                self.hangover_remaining = self.hangover_frames;
+            } else {
+                self.noise_floor =
+                    NOISE_FLOOR_DECAY * self.noise_floor + (1.0 - NOISE_FLOOR_DECAY) * energy;
+                if self.hangover_remaining > 0 {
+                    self.hangover_remaining -= 1;
+                }
+            }
+
+            if is_speech_frame || self.hangover_remaining > 0 {
+                any_speech = true;
+            }
+        }
+        self.pending.drain(0..consumed);
+        any_speech
+    }
+}
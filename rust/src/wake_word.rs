@@ -0,0 +1,108 @@
+// wake_word.rs
+//
+// Keyword-spotting match logic for `Commands::Listen`'s wake-word mode.
+// Kept separate from `ui.rs`'s dispatch loop, the same way `hue.rs` was
+// split out, so the matching rules (exact substring, phonetic fallback)
+// can be reasoned about on their own.
+
+/// Whether `transcript` (whatever the recognizer heard from a short
+/// candidate utterance) counts as the configured wake word having been
+/// spoken: either `wake_word` appears verbatim (case-insensitive), or, if
+/// set, some word in the transcript has the same Soundex code as
+/// `wake_phonetic` — more forgiving of how the recognizer spells an unusual
+/// trigger phrase.
+pub fn wake_word_heard(transcript: &str, wake_word: &str, wake_phonetic: Option<&str>) -> bool {
+    if wake_word.is_empty() {
+        return false;
+    }
+    if transcript.to_lowercase().contains(&wake_word.to_lowercase()) {
+        return true;
+    }
+    let Some(wake_phonetic) = wake_phonetic else {
+        return false;
+    };
+    let target = soundex(wake_phonetic);
+    !target.is_empty() && transcript.split_whitespace().any(|word| soundex(word) == target)
+}
+
+/// A minimal Soundex implementation: keep the first letter, map the rest to
+/// a digit class (dropping vowels/H/W/Y and collapsing adjacent duplicates),
+/// then pad or truncate to 4 characters. Good enough to catch a recognizer
+/// mishearing one trigger phrase for another without pulling in a dedicated
+/// phonetic-matching crate for a single feature.
+fn soundex(word: &str) -> String {
+    fn class(c: char) -> Option<char> {
+        match c.to_ascii_uppercase() {
+            'B' | 'F' | 'P' | 'V' => Some('1'),
+            'C' | 'G' | 'J' | 'K' | 'Q' | 'S' | 'X' | 'Z' => Some('2'),
+            'D' | 'T' => Some('3'),
+            'L' => Some('4'),
+            'M' | 'N' => Some('5'),
+            'R' => Some('6'),
+            _ => None, // vowels, H, W, Y
+        }
+    }
+
+    let chars: Vec<char> = word.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+    let Some(&first) = chars.first() else {
+        return String::new();
+    };
+
+    let mut code = first.to_ascii_uppercase().to_string();
+    let mut last_class = class(first);
+    for &c in &chars[1..] {
+        let this_class = class(c);
+        if let Some(digit) = this_class {
+            if this_class != last_class {
+                code.push(digit);
+            }
+        }
+        last_class = this_class;
+    }
+    code.truncate(4);
+    while code.len() < 4 {
+        code.push('0');
+    }
+    code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soundex_matches_classic_examples() {
+        assert_eq!(soundex("Robert"), "R163");
+        assert_eq!(soundex("Rupert"), "R163");
+        // This implementation doesn't special-case H/W as "bridging"
+        // adjacent same-class letters the way textbook Soundex does, so
+        // the S/C pair in "Ashcraft" codes separately instead of collapsing.
+        assert_eq!(soundex("Ashcraft"), "A226");
+    }
+
+    #[test]
+    fn soundex_of_empty_string_is_empty() {
+        assert_eq!(soundex(""), "");
+    }
+
+    #[test]
+    fn wake_word_heard_matches_verbatim_case_insensitive() {
+        assert!(wake_word_heard("hey Computer please", "computer", None));
+        assert!(!wake_word_heard("hey there", "computer", None));
+    }
+
+    #[test]
+    fn wake_word_heard_falls_back_to_phonetic_match() {
+        // "compooter" isn't a verbatim match but shares "computer"'s Soundex code.
+        assert!(wake_word_heard(
+            "hey compooter",
+            "computer",
+            Some("computer")
+        ));
+    }
+
+    #[test]
+    fn wake_word_heard_requires_non_empty_wake_word() {
+        assert!(!wake_word_heard("anything", "", Some("computer")));
+    }
+}